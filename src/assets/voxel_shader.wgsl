@@ -12,7 +12,8 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
     @location(2) block_id: u32,
-    @location(3) direction: f32
+    @location(3) direction: f32,
+    @location(4) alpha: f32
 };
 
 struct VertexOutput {
@@ -33,7 +34,7 @@ fn vs_main(
         model.color[0]*model.direction, 
         model.color[1]*model.direction,
         model.color[2]*model.direction,
-        1.0
+        model.alpha
     );
     return out;
 }
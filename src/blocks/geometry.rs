@@ -0,0 +1,167 @@
+use crate::direction::AbsolouteDirection;
+
+use super::registry::{BlockDefintion, RenderShape};
+use super::state::{BlockState, Half};
+
+// the space a block takes up inside of its cell, in blocks.
+// (0, 0, 0) to (1, 1, 1) is the whole cell
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BlockBox{
+    pub min: [f32; 3],
+    pub max: [f32; 3]
+}
+
+// a rectangle on one side of a cell, in the two axes along that
+// side. used to tell how much of a side a block covers
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct SideRect{
+    min: [f32; 2],
+    max: [f32; 2]
+}
+
+impl SideRect{
+    fn contains(&self, other: &SideRect) -> bool{
+        self.min[0] <= other.min[0] && self.min[1] <= other.min[1]
+            && self.max[0] >= other.max[0] && self.max[1] >= other.max[1]
+    }
+}
+
+impl BlockBox{
+    pub const FULL: Self = Self{ min: [0.0, 0.0, 0.0], max: [1.0, 1.0, 1.0] };
+
+    // whether the side of the box facing direction lies
+    // on the side of the cell
+    pub fn touches_side(&self, direction: AbsolouteDirection) -> bool{
+        match direction{
+            AbsolouteDirection::East => self.max[0] >= 1.0,
+            AbsolouteDirection::West => self.min[0] <= 0.0,
+            AbsolouteDirection::Up => self.max[1] >= 1.0,
+            AbsolouteDirection::Down => self.min[1] <= 0.0,
+            AbsolouteDirection::North => self.max[2] >= 1.0,
+            AbsolouteDirection::South => self.min[2] <= 0.0
+        }
+    }
+
+    // the box's side facing direction, flattened onto that side
+    fn side_rect(&self, direction: AbsolouteDirection) -> SideRect{
+        let (a, b) = match direction{
+            AbsolouteDirection::East | AbsolouteDirection::West => (2, 1),
+            AbsolouteDirection::North | AbsolouteDirection::South => (0, 1),
+            AbsolouteDirection::Up | AbsolouteDirection::Down => (0, 2)
+        };
+        SideRect{ min: [self.min[a], self.min[b]], max: [self.max[a], self.max[b]] }
+    }
+
+    // turns a box made facing north to face another way
+    fn rotate(&self, facing: AbsolouteDirection) -> Self{
+        let turn = |x: f32, z: f32| match facing{
+            AbsolouteDirection::East => (z, 1.0 - x),
+            AbsolouteDirection::South => (1.0 - x, 1.0 - z),
+            AbsolouteDirection::West => (1.0 - z, x),
+            _ => (x, z)
+        };
+        let (ax, az) = turn(self.min[0], self.min[2]);
+        let (bx, bz) = turn(self.max[0], self.max[2]);
+        Self{
+            min: [ax.min(bx), self.min[1], az.min(bz)],
+            max: [ax.max(bx), self.max[1], az.max(bz)]
+        }
+    }
+
+    fn flip_y(&self) -> Self{
+        Self{
+            min: [self.min[0], 1.0 - self.max[1], self.min[2]],
+            max: [self.max[0], 1.0 - self.min[1], self.max[2]]
+        }
+    }
+}
+
+// the boxes a block is made of in a given state. cross shaped
+// and invisible blocks have none
+pub fn block_boxes(def: &BlockDefintion, state: BlockState) -> Vec<BlockBox>{
+    let bottom = BlockBox{ min: [0.0, 0.0, 0.0], max: [1.0, 0.5, 1.0] };
+    let boxes = match &def.shape{
+        RenderShape::None | RenderShape::Cross => return Vec::new(),
+        RenderShape::Cube => return vec![BlockBox::FULL],
+        RenderShape::Slab => vec![bottom],
+        // the step sits on the north half before being turned
+        RenderShape::Stair => vec![
+            bottom,
+            BlockBox{ min: [0.0, 0.5, 0.5], max: [1.0, 1.0, 1.0] }.rotate(state.facing)
+        ],
+        RenderShape::Boxes(boxes) if def.properties.facing => {
+            boxes.iter().map(|b| b.rotate(state.facing)).collect()
+        },
+        RenderShape::Boxes(boxes) => boxes.clone()
+    };
+    // slabs and stairs can be flipped to hang from the top
+    match state.half{
+        Half::Top if def.properties.half => boxes.iter().map(|b| b.flip_y()).collect(),
+        _ => boxes
+    }
+}
+
+// whether a face lying on the side of a cell is hidden by the
+// boxes of the block on the other side of it. a face is only
+// hidden if one box covers all of it
+pub fn side_is_covered(face: &BlockBox, side: AbsolouteDirection, neighbor: &[BlockBox]) -> bool{
+    let rect = face.side_rect(side);
+    neighbor.iter()
+        .filter(|b| b.touches_side(side.opposite()))
+        .any(|b| b.side_rect(side).contains(&rect))
+}
+
+// the color of one side of a block. the two sides a block is
+// facing along can have their own color, and blocks that age
+// turn from their color to their end color as they grow
+pub fn face_color(def: &BlockDefintion, state: BlockState, side: AbsolouteDirection) -> [f32; 3]{
+    match def.end_color{
+        Some(end) if def.properties.facing && (side == state.facing || side == state.facing.opposite()) => end,
+        Some(end) if def.properties.age => {
+            let t = (state.age as f32 / def.max_age as f32).min(1.0);
+            [0, 1, 2].map(|i| def.color[i] + (end[i] - def.color[i]) * t)
+        },
+        _ => def.color
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::BlockRegistry;
+
+    fn registry() -> BlockRegistry{
+        BlockRegistry::parse("[stone]\n[slab]\nshape = slab\nproperties = half\n[stairs]\nshape = stair\nproperties = facing half\n").unwrap()
+    }
+
+    #[test]
+    fn stairs_turn_with_facing(){
+        let registry = registry();
+        let stairs = registry.expect_id("stairs");
+        let east = BlockState::new(stairs).with_facing(AbsolouteDirection::East);
+        let boxes = block_boxes(registry.get(stairs), east);
+        assert_eq!(boxes[1], BlockBox{ min: [0.5, 0.5, 0.0], max: [1.0, 1.0, 1.0] });
+        // upside down stairs have their step on the bottom
+        let boxes = block_boxes(registry.get(stairs), east.with_half(Half::Top));
+        assert_eq!(boxes[0], BlockBox{ min: [0.0, 0.5, 0.0], max: [1.0, 1.0, 1.0] });
+        assert_eq!(boxes[1], BlockBox{ min: [0.5, 0.0, 0.0], max: [1.0, 0.5, 1.0] });
+    }
+
+    #[test]
+    fn slabs_only_cover_half_a_side(){
+        let registry = registry();
+        let slab = registry.expect_id("slab");
+        let bottom = block_boxes(registry.get(slab), BlockState::new(slab));
+        let top = block_boxes(registry.get(slab), BlockState::new(slab).with_half(Half::Top));
+        let full = [BlockBox::FULL];
+        // the side of a cube next to a slab is only half hidden
+        assert!(!side_is_covered(&BlockBox::FULL, AbsolouteDirection::East, &bottom));
+        // but two slabs next to each other hide each other's sides
+        assert!(side_is_covered(&bottom[0], AbsolouteDirection::East, &bottom));
+        assert!(!side_is_covered(&bottom[0], AbsolouteDirection::East, &top));
+        assert!(side_is_covered(&bottom[0], AbsolouteDirection::East, &full));
+        // a bottom slab covers the top of the block under it
+        assert!(side_is_covered(&BlockBox::FULL, AbsolouteDirection::Up, &bottom));
+        assert!(!side_is_covered(&BlockBox::FULL, AbsolouteDirection::Up, &top));
+    }
+}
\ No newline at end of file
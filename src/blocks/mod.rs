@@ -1,99 +1,188 @@
-use bracket_noise::prelude::FastNoise;
+use std::io;
 
-use crate::render::voxel::{VoxelMesh, INVERSE_VOXEL_WIDTH};
+use lazy_static::lazy_static;
 
-mod stones;
-mod plants;
+use crate::coords::ChunkId;
+use crate::save::BlockIdMap;
+use crate::save::bytes::{ByteReader, ByteWriter, invalid_data};
+
+mod palette;
+mod section;
+mod registry;
+mod state;
+mod geometry;
+
+pub use palette::SECTION_HEIGHT;
+pub use section::{ChunkSection, SectionState};
+pub use registry::{BlockRegistry, RenderShape};
+pub use state::{block_of, BlockState, Half};
+pub use geometry::{block_boxes, face_color, side_is_covered, BlockBox};
 
 pub const CHUNK_WIDTH: usize = 16;
 pub const CHUNK_HEIGHT: usize = 128;
-// idk y, but the chunks are offset by this value.
-// so chunk 0, 0 spawns 8 chunks away from the world 
-// coord 0, 0
-const WORLD_OFFSET: f32 = 8.0;
-pub type ChunkData = [[[usize; CHUNK_WIDTH]; CHUNK_HEIGHT]; CHUNK_WIDTH];
+pub const SECTIONS_PER_CHUNK: usize = CHUNK_HEIGHT / SECTION_HEIGHT;
+// air is the only block with a fixed id
+pub const AIR_ID: usize = 0;
+// read at startup if it's there, relative to where the game is
+// run from, so blocks can be changed without rebuilding
+const BLOCK_FILE: &str = "assets/blocks.def";
+
+// saves keep their own name to id map, so a block file that
+// adds, removes or reorders blocks still loads old worlds
+fn load_blocks() -> BlockRegistry{
+    match std::fs::read_to_string(BLOCK_FILE){
+        Ok(text) => BlockRegistry::parse(&text)
+            .unwrap_or_else(|e| panic!("Invalid block file {}: {}", BLOCK_FILE, e)),
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound{
+                eprintln!("Failed to read {}, using the built in blocks: {}", BLOCK_FILE, e);
+            }
+            BlockRegistry::parse(include_str!("../assets/blocks.def")).expect("Invalid built in block file")
+        }
+    }
+}
+
+lazy_static!{
+    // every block definition, from BLOCK_FILE or the copy of
+    // assets/blocks.def built into the game
+    pub static ref BLOCKS: BlockRegistry = load_blocks();
+    // blocks that world generation places by name
+    pub static ref GRASS_ID: usize = BLOCKS.expect_id("grass");
+    pub static ref DIRT_ID: usize = BLOCKS.expect_id("dirt");
+    pub static ref STONE_ID: usize = BLOCKS.expect_id("stone");
+    pub static ref SAND_ID: usize = BLOCKS.expect_id("sand");
+    pub static ref SNOW_ID: usize = BLOCKS.expect_id("snow");
+    pub static ref WATER_ID: usize = BLOCKS.expect_id("water");
+    pub static ref LOG_ID: usize = BLOCKS.expect_id("log");
+    pub static ref LEAVES_ID: usize = BLOCKS.expect_id("leaves");
+    pub static ref TALL_GRASS_ID: usize = BLOCKS.expect_id("tall_grass");
+    pub static ref FLOWER_ID: usize = BLOCKS.expect_id("flower");
+    // blocks with behavior
+    pub static ref WHEAT_ID: usize = BLOCKS.expect_id("wheat");
+}
 
-// picture it as coordinates on a map
+// one SECTION_HEIGHT tall slice of a chunk
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct ChunkId{
-    pub x: i32,
-    pub z: i32
+pub struct SectionId{
+    pub chunk: ChunkId,
+    // 0 is the bottom section
+    pub index: usize
+}
+
+impl SectionId{
+    pub fn new(chunk: ChunkId, index: usize) -> Self{
+        Self{ chunk, index }
+    }
 }
 
 // attempts to make this the world failed when I 
 // caused a segfault :(
 pub struct Chunk{
-    // [x][y][z]
     id: ChunkId,
-    blocks: ChunkData,
-    pub mesh: VoxelMesh
+    // bottom to top, each SECTION_HEIGHT blocks tall
+    sections: Vec<ChunkSection>,
+    // changed since it was generated or loaded, so it needs saving
+    modified: bool
 }
 
 impl Chunk{
-    pub fn flat_world(id: ChunkId, height_map: &FastNoise, device: &wgpu::Device) -> Self{
-        let mut blocks: ChunkData = [[[0usize; CHUNK_WIDTH]; CHUNK_HEIGHT]; CHUNK_WIDTH];
-    
-        for x in 0..CHUNK_WIDTH{
-            for z in 0..CHUNK_WIDTH{
-                let height_dif = height_map.get_noise(
-                    (x as f32 + CHUNK_WIDTH as f32*id.x as f32)/160.0, (z as f32 + CHUNK_WIDTH as f32 * id.z as f32)/100.0
-                ) * 50.0;
-                // turning height_dif to usize before adding it to 32
-                // causes a cool floor effect
-                let height = (32.0 + height_dif) as usize;
-                println!("dif: {}", height_dif);
-                println!("Height: {}", height);
-                for y in 0..height{
-                    let mut block = 3;
-                    if y == height-1{ block = 1; }
-                    if y < height-1 && y >= height - 4{ block = 2; }
-                    blocks[x][y][z] = block;
-                }
-            }
-        }
+    pub fn empty(id: ChunkId) -> Self{
+        let mut sections = Vec::with_capacity(SECTIONS_PER_CHUNK);
+        sections.resize_with(SECTIONS_PER_CHUNK, ChunkSection::empty);
+        Self{ id, sections, modified: false }
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> usize{
+        self.sections[y / SECTION_HEIGHT].get(x, y % SECTION_HEIGHT, z)
+    }
 
-        let mesh = VoxelMesh::from_blocks(&id, &blocks, device);
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: usize){
+        self.sections[y / SECTION_HEIGHT].set(x, y % SECTION_HEIGHT, z, block);
+    }
 
-        Self{ id, blocks, mesh }
+    pub fn get_id(&self) -> ChunkId{
+        self.id
     }
 
-    pub fn get_block(&self, x: usize, y: usize, z: usize) -> usize{
-        self.blocks[x][y][z]
+    pub fn get_section(&self, index: usize) -> &ChunkSection{
+        &self.sections[index]
     }
-}
 
-pub fn world_coord_to_chunk_id(x: f32, z: f32) -> ChunkId{
-    let c_x = (x * INVERSE_VOXEL_WIDTH / CHUNK_WIDTH as f32).floor();
-    let c_z = (z * INVERSE_VOXEL_WIDTH / CHUNK_WIDTH as f32).floor();
-    ChunkId { x: c_x as i32, z: c_z as i32 }
+    pub fn is_modified(&self) -> bool{
+        self.modified
+    }
+
+    pub fn set_modified(&mut self, modified: bool){
+        self.modified = modified;
+    }
+
+    pub fn write(&self, w: &mut ByteWriter, ids: &BlockIdMap){
+        w.write_u8(self.sections.len() as u8);
+        for section in &self.sections{
+            section.write(w, ids);
+        }
+    }
+
+    pub fn read(id: ChunkId, r: &mut ByteReader, ids: &BlockIdMap) -> io::Result<Self>{
+        let count = r.read_u8()? as usize;
+        if count != SECTIONS_PER_CHUNK{
+            return Err(invalid_data("chunk has the wrong number of sections"));
+        }
+        let mut sections = Vec::with_capacity(SECTIONS_PER_CHUNK);
+        for _ in 0..count{
+            sections.push(ChunkSection::read(r, ids)?);
+        }
+        Ok(Self{ id, sections, modified: false })
+    }
 }
 
-pub struct BlockDefintion{
-    pub transparency: bool,
-    pub color: [f32; 3]
+// a chunk along with the four chunks that border it. lets
+// meshing look past the edge of a chunk instead of
+// assuming everything outside of it is air
+pub struct ChunkNeighbors<'a>{
+    pub center: &'a Chunk,
+    // +z
+    pub north: Option<&'a Chunk>,
+    // -z
+    pub south: Option<&'a Chunk>,
+    // +x
+    pub east: Option<&'a Chunk>,
+    // -x
+    pub west: Option<&'a Chunk>
 }
 
-pub const AIR: BlockDefintion = BlockDefintion{
-    transparency: true,
-    color: [0.0, 0.0, 0.0]
-};
-
-pub const BLOCK_ARRAY: [BlockDefintion; 16] = [
-    AIR,
-    plants::GRASS,
-    stones::DIRT,
-    stones::STONE,
-    // padding
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR,
-    AIR
-];
\ No newline at end of file
+impl<'a> ChunkNeighbors<'a>{
+    // takes coordinates local to the center chunk, which can go
+    // one block past its edges. returns None if the block is
+    // above or below the world or in a chunk that isn't loaded
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Option<usize>{
+        if y < 0 || y >= CHUNK_HEIGHT as i32{ return None; }
+        let width = CHUNK_WIDTH as i32;
+        let (chunk, x, z) = match (x, z){
+            (x, _) if x < 0 => (self.west?, x + width, z),
+            (x, _) if x >= width => (self.east?, x - width, z),
+            (_, z) if z < 0 => (self.south?, x, z + width),
+            (_, z) if z >= width => (self.north?, x, z - width),
+            _ => (self.center, x, z)
+        };
+        Some(chunk.get_block(x as usize, y as usize, z as usize))
+    }
+
+    // whether a section and the six around it are all opaque,
+    // meaning not a single face in it can be seen
+    pub fn section_is_hidden(&self, index: usize) -> bool{
+        let opaque = |chunk: Option<&Chunk>, index: usize| match chunk{
+            Some(c) => c.get_section(index).get_state() == SectionState::Opaque,
+            None => false
+        };
+        // the bottom of the world is left visible like the
+        // rest of the world's edges
+        opaque(Some(self.center), index)
+            && index > 0 && opaque(Some(self.center), index - 1)
+            && index + 1 < SECTIONS_PER_CHUNK && opaque(Some(self.center), index + 1)
+            && opaque(self.north, index)
+            && opaque(self.south, index)
+            && opaque(self.east, index)
+            && opaque(self.west, index)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,294 @@
+use std::io;
+
+use crate::save::BlockIdMap;
+use crate::save::bytes::{ByteReader, ByteWriter, invalid_data};
+
+use super::{AIR_ID, CHUNK_WIDTH};
+
+pub const SECTION_HEIGHT: usize = 16;
+pub const SECTION_VOLUME: usize = CHUNK_WIDTH * SECTION_HEIGHT * CHUNK_WIDTH;
+
+// stores a 16x16x16 cube of blocks.
+// instead of keeping a full usize for every block, each distinct
+// block is put in the palette once and the blocks are stored as
+// indices into it, packed as tightly as the palette size allows.
+// a section of only one block (ex: all air or all stone) has a
+// palette of 1 and stores no indices at all
+pub struct PalettedStorage{
+    palette: Vec<usize>,
+    // bits used per index. 0 means everything is palette[0]
+    bits: u32,
+    // indices are never split across two words
+    data: Vec<u64>,
+    // number of blocks that aren't air
+    non_air: u16
+}
+
+fn index(x: usize, y: usize, z: usize) -> usize{
+    (y * CHUNK_WIDTH + z) * CHUNK_WIDTH + x
+}
+
+// smallest number of bits that can index a palette of this size
+fn bits_for(palette_len: usize) -> u32{
+    if palette_len <= 1{ return 0; }
+    usize::BITS - (palette_len - 1).leading_zeros()
+}
+
+impl PalettedStorage{
+    pub fn empty() -> Self{
+        Self::filled(AIR_ID)
+    }
+
+    pub fn filled(block: usize) -> Self{
+        Self{
+            palette: vec![block],
+            bits: 0,
+            data: Vec::new(),
+            non_air: if block == AIR_ID{ 0 }else{ SECTION_VOLUME as u16 }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.non_air == 0
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> usize{
+        // all air and single block sections never touch data
+        if self.bits == 0{ return self.palette[0]; }
+        self.palette[self.get_index(index(x, y, z))]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: usize){
+        let i = index(x, y, z);
+        let old = self.get(x, y, z);
+        if old == block{ return; }
+
+        if old == AIR_ID{ self.non_air += 1; }
+        if block == AIR_ID{ self.non_air -= 1; }
+        // the last block was removed, so drop the palette and data
+        if self.non_air == 0{
+            *self = Self::empty();
+            return;
+        }
+
+        let palette_index = match self.palette.iter().position(|b| *b == block){
+            Some(p) => p,
+            None => {
+                // entries nothing uses anymore are dropped before the
+                // palette is made wider to fit another one
+                if bits_for(self.palette.len() + 1) > self.bits{
+                    if let Some(compact) = self.compacted(){
+                        let non_air = self.non_air;
+                        *self = compact;
+                        self.non_air = non_air;
+                    }
+                }
+                self.palette.push(block);
+                let needed = bits_for(self.palette.len());
+                if needed > self.bits{
+                    self.repack(needed);
+                }
+                self.palette.len() - 1
+            }
+        };
+        self.set_index(i, palette_index);
+    }
+
+    // the palette is written with save ids and the packed data as
+    // is. bits and the number of words come from the palette size
+    pub fn write(&self, w: &mut ByteWriter, ids: &BlockIdMap){
+        // blocks that were all replaced are left out of the save
+        if let Some(compact) = self.compacted(){
+            return compact.write(w, ids);
+        }
+        w.write_u16(self.palette.len() as u16);
+        for block in &self.palette{
+            w.write_u32(ids.save_state(*block));
+        }
+        for word in &self.data{
+            w.write_u64(*word);
+        }
+    }
+
+    pub fn read(r: &mut ByteReader, ids: &BlockIdMap) -> io::Result<Self>{
+        let palette_len = r.read_u16()? as usize;
+        if palette_len == 0{
+            return Err(invalid_data("section has an empty palette"));
+        }
+        let mut palette = Vec::with_capacity(palette_len);
+        for _ in 0..palette_len{
+            palette.push(ids.runtime_state(r.read_u32()?)?);
+        }
+        let mut storage = Self{ palette, bits: bits_for(palette_len), data: Vec::new(), non_air: 0 };
+        if storage.bits == 0{
+            if storage.palette[0] != AIR_ID{ storage.non_air = SECTION_VOLUME as u16; }
+            return Ok(storage);
+        }
+
+        let per_word = storage.indices_per_word();
+        let words = SECTION_VOLUME.div_ceil(per_word);
+        storage.data.reserve_exact(words);
+        for _ in 0..words{
+            storage.data.push(r.read_u64()?);
+        }
+        for i in 0..SECTION_VOLUME{
+            let palette_index = storage.get_index(i);
+            if palette_index >= palette_len{
+                return Err(invalid_data("section index is outside of its palette"));
+            }
+            if storage.palette[palette_index] != AIR_ID{ storage.non_air += 1; }
+        }
+        if storage.non_air == 0{
+            return Ok(Self::empty());
+        }
+        Ok(storage)
+    }
+
+    // a copy without the palette entries nothing uses anymore,
+    // or None if every entry is still used. set only does this
+    // when the palette would grow, so it's done again on saving
+    fn compacted(&self) -> Option<Self>{
+        if self.bits == 0{ return None; }
+        let mut used = vec![false; self.palette.len()];
+        for i in 0..SECTION_VOLUME{
+            used[self.get_index(i)] = true;
+        }
+        if used.iter().all(|u| *u){ return None; }
+
+        let mut remap = vec![0; self.palette.len()];
+        let mut palette = Vec::new();
+        for (old, block) in self.palette.iter().enumerate(){
+            if used[old]{
+                remap[old] = palette.len();
+                palette.push(*block);
+            }
+        }
+        if palette.len() == 1{
+            return Some(Self::filled(palette[0]));
+        }
+        let bits = bits_for(palette.len());
+        let mut compact = Self{ palette, bits: 0, data: Vec::new(), non_air: self.non_air };
+        compact.repack(bits);
+        for i in 0..SECTION_VOLUME{
+            compact.set_index(i, remap[self.get_index(i)]);
+        }
+        Some(compact)
+    }
+
+    fn indices_per_word(&self) -> usize{
+        (u64::BITS / self.bits) as usize
+    }
+
+    fn get_index(&self, i: usize) -> usize{
+        let per_word = self.indices_per_word();
+        let shift = (i % per_word) as u32 * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+        ((self.data[i / per_word] >> shift) & mask) as usize
+    }
+
+    fn set_index(&mut self, i: usize, palette_index: usize){
+        let per_word = self.indices_per_word();
+        let shift = (i % per_word) as u32 * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+        let word = &mut self.data[i / per_word];
+        *word = (*word & !(mask << shift)) | ((palette_index as u64) << shift);
+    }
+
+    // moves every index over to a wider bit width
+    fn repack(&mut self, bits: u32){
+        let old_bits = self.bits;
+        let old_data = std::mem::take(&mut self.data);
+        let old_per_word = u64::BITS.checked_div(old_bits).unwrap_or(0) as usize;
+
+        self.bits = bits;
+        let per_word = self.indices_per_word();
+        self.data = vec![0u64; SECTION_VOLUME.div_ceil(per_word)];
+        // a 0 bit section was all palette[0], which the zeroed data already says
+        if old_bits == 0{ return; }
+
+        let old_mask = (1u64 << old_bits) - 1;
+        for i in 0..SECTION_VOLUME{
+            let shift = (i % old_per_word) as u32 * old_bits;
+            let palette_index = (old_data[i / old_per_word] >> shift) & old_mask;
+            self.set_index(i, palette_index as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::{BLOCKS, DIRT_ID, STONE_ID};
+
+    #[test]
+    fn blocks_survive_the_palette_growing(){
+        let mut storage = PalettedStorage::empty();
+        // 2, 3, 5, 17 and 200 blocks need 1, 2, 4, 8 and 8 bits
+        for count in [2, 3, 5, 17, 200]{
+            for block in 1..count{
+                storage.set(block % 16, block / 16, 3, block);
+            }
+            assert_eq!(storage.bits, bits_for(count));
+            for block in 1..count{
+                assert_eq!(storage.get(block % 16, block / 16, 3), block);
+            }
+            assert_eq!(storage.get(15, 15, 15), AIR_ID);
+        }
+        assert_eq!(storage.bits, 8);
+    }
+
+    #[test]
+    fn air_is_counted(){
+        let mut storage = PalettedStorage::empty();
+        assert!(storage.is_empty() && storage.data.is_empty());
+        storage.set(1, 2, 3, 7);
+        storage.set(4, 5, 6, 7);
+        assert_eq!(storage.non_air, 2);
+        storage.set(1, 2, 3, AIR_ID);
+        assert_eq!(storage.non_air, 1);
+        // taking out the last block goes back to no data at all
+        storage.set(4, 5, 6, AIR_ID);
+        assert!(storage.is_empty() && storage.data.is_empty());
+        assert_eq!(storage.palette, vec![AIR_ID]);
+        let full = PalettedStorage::filled(7);
+        assert_eq!(full.non_air, SECTION_VOLUME as u16);
+        assert_eq!(full.get(9, 9, 9), 7);
+    }
+
+    #[test]
+    fn unused_blocks_are_dropped_before_growing(){
+        let mut storage = PalettedStorage::empty();
+        storage.set(0, 0, 0, 5);
+        storage.set(1, 0, 0, 6);
+        storage.set(2, 0, 0, 7);
+        assert_eq!(storage.bits, 2);
+        storage.set(1, 0, 0, AIR_ID);
+        // 6 is gone, so 8 fits without going to 3 bits
+        storage.set(3, 0, 0, 8);
+        assert_eq!(storage.palette, vec![AIR_ID, 5, 7, 8]);
+        assert_eq!(storage.bits, 2);
+        assert_eq!(storage.non_air, 3);
+        let blocks: Vec<usize> = (0..4).map(|x| storage.get(x, 0, 0)).collect();
+        assert_eq!(blocks, vec![5, AIR_ID, 7, 8]);
+    }
+
+    #[test]
+    fn saving_drops_unused_blocks(){
+        let ids = BlockIdMap::new(Vec::new(), &BLOCKS);
+        let mut storage = PalettedStorage::empty();
+        storage.set(0, 0, 0, *STONE_ID);
+        storage.set(1, 0, 0, *DIRT_ID);
+        storage.set(2, 0, 0, 7);
+        storage.set(2, 0, 0, AIR_ID);
+        let mut w = ByteWriter::new();
+        storage.write(&mut w, &ids);
+        let data = w.into_inner();
+        let read = PalettedStorage::read(&mut ByteReader::new(&data), &ids).unwrap();
+        assert_eq!(read.palette, vec![AIR_ID, *STONE_ID, *DIRT_ID]);
+        assert_eq!(read.bits, 2);
+        assert_eq!(read.non_air, 2);
+        assert_eq!(read.get(0, 0, 0), *STONE_ID);
+        assert_eq!(read.get(1, 0, 0), *DIRT_ID);
+        assert_eq!(read.get(2, 0, 0), AIR_ID);
+    }
+}
\ No newline at end of file
@@ -1,6 +0,0 @@
-use super::BlockDefintion;
-
-pub const GRASS: BlockDefintion = BlockDefintion{
-    transparency: false,
-    color: [0.0, 1.0, 0.0]
-};
\ No newline at end of file
@@ -0,0 +1,283 @@
+use std::io;
+
+use hashbrown::HashMap;
+
+use crate::save::bytes::invalid_data;
+
+use super::geometry::BlockBox;
+use super::state::{block_of, BLOCK_MASK, MAX_AGE};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderShape{
+    // nothing gets drawn, ex: air
+    None,
+    Cube,
+    // the top or bottom half of a cube, picked by the half property
+    Slab,
+    // a slab with a step on the side it is facing
+    Stair,
+    // two crossed quads, for plants
+    Cross,
+    // any boxes, turned to match the facing property if it has one
+    Boxes(Vec<BlockBox>)
+}
+
+impl RenderShape{
+    fn from_name(name: &str) -> Option<Self>{
+        match name{
+            "none" => Some(Self::None),
+            "cube" => Some(Self::Cube),
+            "slab" => Some(Self::Slab),
+            "stair" => Some(Self::Stair),
+            "cross" => Some(Self::Cross),
+            _ => None
+        }
+    }
+}
+
+// boxes are written in sixteenths of a block, as
+// x0 y0 z0 x1 y1 z1 with a comma between each box
+fn parse_boxes(text: &str) -> Option<Vec<BlockBox>>{
+    let mut boxes = Vec::new();
+    for part in text.split(','){
+        let n: Vec<f32> = part.split_whitespace()
+            .map(|p| p.parse::<f32>().map(|v| v / 16.0))
+            .collect::<Result<_, _>>()
+            .ok()?;
+        if n.len() != 6{ return None; }
+        let b = BlockBox{ min: [n[0], n[1], n[2]], max: [n[3], n[4], n[5]] };
+        let in_cell = b.min.iter().chain(&b.max).all(|v| (0.0..=1.0).contains(v));
+        if !in_cell || (0..3).any(|i| b.min[i] >= b.max[i]){ return None; }
+        boxes.push(b);
+    }
+    Some(boxes)
+}
+
+// the parts of a block state a block makes use of
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockProperties{
+    pub facing: bool,
+    pub half: bool,
+    pub open: bool,
+    pub age: bool
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockDefintion{
+    pub name: String,
+    pub transparency: bool,
+    pub color: [f32; 3],
+    // below 1 the block is drawn see through in the translucent pass
+    pub alpha: f32,
+    // color of the two faces the block is facing along, ex: log rings
+    pub end_color: Option<[f32; 3]>,
+    // read from blocks.def but not used yet. kept for when
+    // breaking a block takes time
+    pub hardness: f32,
+    pub shape: RenderShape,
+    pub properties: BlockProperties,
+    // the oldest a block with the age property gets, ex: a ripe crop
+    pub max_age: u8,
+    // falls when there's nothing under it, ex: sand
+    pub falls: bool,
+    // whether things bump into it. solid blocks collide as
+    // a whole cube whatever their shape
+    pub solid: bool,
+    // things inside of it can move up and down, ex: a ladder
+    pub climbable: bool
+}
+
+impl BlockDefintion{
+    fn new(name: &str) -> Self{
+        Self{
+            name: name.into(),
+            transparency: false,
+            color: [0.0, 0.0, 0.0],
+            alpha: 1.0,
+            end_color: None,
+            hardness: 1.0,
+            shape: RenderShape::Cube,
+            properties: BlockProperties::default(),
+            max_age: MAX_AGE,
+            falls: false,
+            solid: true,
+            climbable: false
+        }
+    }
+
+    // whether the block fills its whole cell and can't be seen
+    // through, so nothing behind it can be seen either
+    pub fn is_opaque_cube(&self) -> bool{
+        !self.transparency && self.shape == RenderShape::Cube
+    }
+
+    // drawn with blending after everything solid
+    pub fn is_translucent(&self) -> bool{
+        self.alpha < 1.0
+    }
+
+    fn air() -> Self{
+        Self{
+            transparency: true,
+            hardness: 0.0,
+            shape: RenderShape::None,
+            solid: false,
+            ..Self::new("air")
+        }
+    }
+}
+
+// every block the game knows about. a block's id is its
+// position in the list, which is only decided at runtime
+pub struct BlockRegistry{
+    blocks: Vec<BlockDefintion>,
+    ids: HashMap<String, usize>
+}
+
+impl BlockRegistry{
+    // reads a block file, see assets/blocks.def for the format.
+    // air doesn't need to be in the file, it is always AIR_ID
+    pub fn parse(text: &str) -> io::Result<Self>{
+        let mut registry = Self{ blocks: Vec::new(), ids: HashMap::new() };
+        registry.add(BlockDefintion::air())?;
+
+        let mut current: Option<BlockDefintion> = None;
+        for (i, line) in text.lines().enumerate(){
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty(){ continue; }
+            let error = |msg: &str| invalid_data(&format!("block file line {}: {}", i + 1, msg));
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')){
+                if let Some(done) = current.take(){ registry.add(done)?; }
+                current = Some(BlockDefintion::new(name.trim()));
+                continue;
+            }
+            let block = current.as_mut().ok_or_else(|| error("expected a [name] first"))?;
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected key = value"))?;
+            let value = value.trim();
+            let color = || -> io::Result<[f32; 3]>{
+                let parts: Vec<f32> = value.split_whitespace()
+                    .map(|p| p.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| error("color should be three numbers"))?;
+                parts.try_into().map_err(|_| error("color should be three numbers"))
+            };
+            match key.trim(){
+                "color" => block.color = color()?,
+                "end_color" => block.end_color = Some(color()?),
+                "alpha" => block.alpha = value.parse::<f32>()
+                    .ok()
+                    .filter(|a| (0.0..=1.0).contains(a))
+                    .ok_or_else(|| error("alpha should be a number from 0 to 1"))?,
+                "properties" => for property in value.split_whitespace(){
+                    match property{
+                        "facing" => block.properties.facing = true,
+                        "half" => block.properties.half = true,
+                        "open" => block.properties.open = true,
+                        "age" => block.properties.age = true,
+                        p => return Err(error(&format!("unknown property: {}", p)))
+                    }
+                },
+                "max_age" => block.max_age = value.parse::<u8>()
+                    .ok()
+                    .filter(|a| (1..=MAX_AGE).contains(a))
+                    .ok_or_else(|| error(&format!("max_age should be a number from 1 to {}", MAX_AGE)))?,
+                "solid" => block.solid = value.parse().map_err(|_| error("solid should be true or false"))?,
+                "climbable" => block.climbable = value.parse().map_err(|_| error("climbable should be true or false"))?,
+                "falls" => block.falls = value.parse().map_err(|_| error("falls should be true or false"))?,
+                "transparent" => block.transparency = value.parse().map_err(|_| error("transparent should be true or false"))?,
+                "hardness" => block.hardness = value.parse().map_err(|_| error("hardness should be a number"))?,
+                "shape" => block.shape = RenderShape::from_name(value).ok_or_else(|| error("unknown shape"))?,
+                "boxes" => block.shape = RenderShape::Boxes(parse_boxes(value).ok_or_else(|| error("boxes should be six numbers from 0 to 16 each"))?),
+                key => return Err(error(&format!("unknown key: {}", key)))
+            }
+        }
+        if let Some(done) = current{ registry.add(done)?; }
+        Ok(registry)
+    }
+
+    fn add(&mut self, mut block: BlockDefintion) -> io::Result<()>{
+        if self.ids.contains_key(&block.name){
+            return Err(invalid_data(&format!("block {} is defined twice", block.name)));
+        }
+        if self.blocks.len() > BLOCK_MASK{
+            return Err(invalid_data("too many blocks to fit in a block state"));
+        }
+        // anything see through has to let the faces behind it draw
+        if block.is_translucent(){ block.transparency = true; }
+        self.ids.insert(block.name.clone(), self.blocks.len());
+        self.blocks.push(block);
+        Ok(())
+    }
+
+    pub fn get(&self, id: usize) -> &BlockDefintion{
+        &self.blocks[id]
+    }
+
+    // the definition of the block a state is of
+    pub fn of_state(&self, state: usize) -> &BlockDefintion{
+        self.get(block_of(state))
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<usize>{
+        self.ids.get(name).copied()
+    }
+
+    // for blocks the game can't work without
+    pub fn expect_id(&self, name: &str) -> usize{
+        self.id_of(name).unwrap_or_else(|| panic!("block file is missing {}", name))
+    }
+
+    pub fn len(&self) -> usize{
+        self.blocks.len()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str>{
+        self.blocks.iter().map(|b| b.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::AIR_ID;
+
+    #[test]
+    fn ids_follow_the_file(){
+        let registry = BlockRegistry::parse("[stone]\ncolor = 0.5 0.5 0.5\n\n# a comment\n[glass]\nalpha = 0.3\n").unwrap();
+        assert_eq!(registry.id_of("air"), Some(AIR_ID));
+        assert_eq!(registry.id_of("stone"), Some(1));
+        assert_eq!(registry.id_of("glass"), Some(2));
+        assert_eq!(registry.get(1).color, [0.5, 0.5, 0.5]);
+        // see through blocks are always transparent
+        assert!(registry.get(2).transparency);
+        assert!(registry.get(2).is_translucent());
+        assert!(!registry.get(1).is_translucent());
+        assert_eq!(registry.get(2).shape, RenderShape::Cube);
+    }
+
+    #[test]
+    fn box_lists_are_in_sixteenths(){
+        let registry = BlockRegistry::parse("[post]\nboxes = 6 0 6 10 16 10, 0 8 7 16 10 9\n").unwrap();
+        let boxes = match &registry.get(1).shape{
+            RenderShape::Boxes(b) => b.clone(),
+            shape => panic!("expected boxes, got {:?}", shape)
+        };
+        assert_eq!(boxes[0], BlockBox{ min: [0.375, 0.0, 0.375], max: [0.625, 1.0, 0.625] });
+        assert_eq!(boxes[1].max, [1.0, 0.625, 0.5625]);
+    }
+
+    #[test]
+    fn bad_files_are_rejected(){
+        assert!(BlockRegistry::parse("color = 1 1 1").is_err());
+        assert!(BlockRegistry::parse("[stone]\ncolor = 1 1").is_err());
+        assert!(BlockRegistry::parse("[stone]\nshape = blob").is_err());
+        assert!(BlockRegistry::parse("[glass]\nalpha = 1.5").is_err());
+        assert!(BlockRegistry::parse("[wheat]\nmax_age = 16").is_err());
+        assert!(BlockRegistry::parse("[post]\nboxes = 6 0 6 10 17 10").is_err());
+        assert!(BlockRegistry::parse("[post]\nboxes = 6 0 6 10 16").is_err());
+        assert!(BlockRegistry::parse("[stone]\nproperties = facing colour").is_err());
+        assert!(BlockRegistry::parse("[stone]\n[stone]").is_err());
+        assert!(BlockRegistry::parse("[air]").is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,113 @@
+use std::io;
+
+use crate::save::BlockIdMap;
+use crate::save::bytes::{ByteReader, ByteWriter};
+
+use super::{BLOCKS, CHUNK_WIDTH};
+use super::palette::SECTION_HEIGHT;
+use super::palette::{PalettedStorage, SECTION_VOLUME};
+
+// what a section looks like as a whole. lets meshing skip
+// sections that can't have anything to draw
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SectionState{
+    // only air
+    Empty,
+    // every block is solid, so only its outside can show
+    Opaque,
+    Mixed
+}
+
+pub struct ChunkSection{
+    blocks: PalettedStorage,
+    // number of blocks that hide everything behind them
+    opaque: u16
+}
+
+impl ChunkSection{
+    pub fn empty() -> Self{
+        Self{ blocks: PalettedStorage::empty(), opaque: 0 }
+    }
+
+    pub fn get_state(&self) -> SectionState{
+        if self.blocks.is_empty(){ return SectionState::Empty; }
+        if self.opaque as usize == SECTION_VOLUME{ return SectionState::Opaque; }
+        SectionState::Mixed
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> usize{
+        self.blocks.get(x, y, z)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: usize){
+        let old = self.blocks.get(x, y, z);
+        if BLOCKS.of_state(old).is_opaque_cube(){ self.opaque -= 1; }
+        if BLOCKS.of_state(block).is_opaque_cube(){ self.opaque += 1; }
+        self.blocks.set(x, y, z, block);
+    }
+
+    pub fn write(&self, w: &mut ByteWriter, ids: &BlockIdMap){
+        self.blocks.write(w, ids);
+    }
+
+    pub fn read(r: &mut ByteReader, ids: &BlockIdMap) -> io::Result<Self>{
+        let blocks = PalettedStorage::read(r, ids)?;
+        let mut opaque = 0u16;
+        for x in 0..CHUNK_WIDTH{
+            for y in 0..SECTION_HEIGHT{
+                for z in 0..CHUNK_WIDTH{
+                    let block = blocks.get(x, y, z);
+                    if BLOCKS.of_state(block).is_opaque_cube(){ opaque += 1; }
+                }
+            }
+        }
+        Ok(Self{ blocks, opaque })
+    }
+}
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::{AIR_ID, STONE_ID};
+
+    fn each_cell() -> impl Iterator<Item = (usize, usize, usize)>{
+        (0..CHUNK_WIDTH).flat_map(|x| (0..SECTION_HEIGHT).flat_map(move |y| (0..CHUNK_WIDTH).map(move |z| (x, y, z))))
+    }
+
+    #[test]
+    fn filling_and_clearing_changes_the_state(){
+        let mut section = ChunkSection::empty();
+        assert_eq!(section.get_state(), SectionState::Empty);
+        section.set(0, 0, 0, *STONE_ID);
+        assert_eq!(section.get_state(), SectionState::Mixed);
+        for (x, y, z) in each_cell(){
+            section.set(x, y, z, *STONE_ID);
+        }
+        assert_eq!(section.opaque as usize, SECTION_VOLUME);
+        assert_eq!(section.get_state(), SectionState::Opaque);
+        // glass can be seen through
+        section.set(3, 4, 5, BLOCKS.expect_id("glass"));
+        assert_eq!(section.opaque as usize, SECTION_VOLUME - 1);
+        assert_eq!(section.get_state(), SectionState::Mixed);
+        for (x, y, z) in each_cell(){
+            section.set(x, y, z, AIR_ID);
+        }
+        assert_eq!(section.opaque, 0);
+        assert_eq!(section.get_state(), SectionState::Empty);
+    }
+
+    #[test]
+    fn loading_counts_opaque_blocks(){
+        let ids = BlockIdMap::new(Vec::new(), &BLOCKS);
+        let mut section = ChunkSection::empty();
+        for (x, y, z) in each_cell(){
+            section.set(x, y, z, *STONE_ID);
+        }
+        section.set(1, 1, 1, AIR_ID);
+        let mut w = ByteWriter::new();
+        section.write(&mut w, &ids);
+        let data = w.into_inner();
+        let read = ChunkSection::read(&mut ByteReader::new(&data), &ids).unwrap();
+        assert_eq!(read.opaque, section.opaque);
+        assert_eq!(read.get_state(), SectionState::Mixed);
+    }
+}
\ No newline at end of file
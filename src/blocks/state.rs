@@ -0,0 +1,131 @@
+use crate::direction::AbsolouteDirection;
+
+/*
+* A block state is a block id with its properties packed on top
+* of it, so chunks can keep storing plain numbers:
+* - bits 0-11: block id
+* - bits 12-14: facing
+* - bit 15: half
+* - bit 16: open
+* - bits 17-20: age
+* A block with every property at its default has the same state
+* id as its block id, so blocks without properties don't change.
+*/
+
+const BLOCK_BITS: u32 = 12;
+pub const BLOCK_MASK: usize = (1 << BLOCK_BITS) - 1;
+const FACING_SHIFT: u32 = BLOCK_BITS;
+const HALF_SHIFT: u32 = FACING_SHIFT + 3;
+const OPEN_SHIFT: u32 = HALF_SHIFT + 1;
+const AGE_SHIFT: u32 = OPEN_SHIFT + 1;
+pub const MAX_AGE: u8 = 15;
+
+// up comes first so a block that was never turned faces up
+const FACING_ORDER: [AbsolouteDirection; 6] = [
+    AbsolouteDirection::Up,
+    AbsolouteDirection::Down,
+    AbsolouteDirection::North,
+    AbsolouteDirection::East,
+    AbsolouteDirection::South,
+    AbsolouteDirection::West
+];
+
+// which part of the block space a block sits in, ex: slabs
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Half{
+    Bottom,
+    Top
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BlockState{
+    pub block: usize,
+    pub facing: AbsolouteDirection,
+    pub half: Half,
+    pub open: bool,
+    // how far along something that grows is, up to MAX_AGE
+    pub age: u8
+}
+
+// the block a state is of, without its properties
+pub fn block_of(state: usize) -> usize{
+    state & BLOCK_MASK
+}
+
+impl BlockState{
+    // the block with every property at its default
+    pub fn new(block: usize) -> Self{
+        Self{
+            block,
+            facing: AbsolouteDirection::Up,
+            half: Half::Bottom,
+            open: false,
+            age: 0
+        }
+    }
+
+    pub fn from_id(state: usize) -> Self{
+        Self{
+            block: block_of(state),
+            facing: FACING_ORDER[((state >> FACING_SHIFT) & 0b111).min(5)],
+            half: if (state >> HALF_SHIFT) & 1 == 1{ Half::Top }else{ Half::Bottom },
+            open: (state >> OPEN_SHIFT) & 1 == 1,
+            age: ((state >> AGE_SHIFT) & MAX_AGE as usize) as u8
+        }
+    }
+
+    pub fn id(&self) -> usize{
+        let facing = FACING_ORDER.iter().position(|f| *f == self.facing).unwrap();
+        (self.block & BLOCK_MASK)
+            | facing << FACING_SHIFT
+            | (self.half as usize) << HALF_SHIFT
+            | (self.open as usize) << OPEN_SHIFT
+            | (self.age.min(MAX_AGE) as usize) << AGE_SHIFT
+    }
+
+    pub fn with_facing(mut self, facing: AbsolouteDirection) -> Self{
+        self.facing = facing;
+        self
+    }
+
+    pub fn with_half(mut self, half: Half) -> Self{
+        self.half = half;
+        self
+    }
+
+    pub fn with_open(mut self, open: bool) -> Self{
+        self.open = open;
+        self
+    }
+
+    pub fn with_age(mut self, age: u8) -> Self{
+        self.age = age.min(MAX_AGE);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn default_state_is_the_block_id(){
+        assert_eq!(BlockState::new(7).id(), 7);
+        assert_eq!(BlockState::from_id(7), BlockState::new(7));
+    }
+
+    #[test]
+    fn properties_round_trip(){
+        let state = BlockState::new(BLOCK_MASK)
+            .with_facing(AbsolouteDirection::West)
+            .with_half(Half::Top)
+            .with_open(true)
+            .with_age(MAX_AGE);
+        assert_eq!(BlockState::from_id(state.id()), state);
+        assert_eq!(block_of(state.id()), BLOCK_MASK);
+        // properties don't leak into each other
+        let facing = BlockState::new(3).with_facing(AbsolouteDirection::Down);
+        assert_eq!(BlockState::from_id(facing.id()).half, Half::Bottom);
+        assert_eq!(BlockState::from_id(facing.id()).age, 0);
+    }
+}
\ No newline at end of file
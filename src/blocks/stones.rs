@@ -1,11 +0,0 @@
-use super::BlockDefintion;
-
-pub const DIRT: BlockDefintion = BlockDefintion{
-    transparency: false,
-    color: [0.545098, 0.270599, 0.0745098]
-};
-
-pub const STONE: BlockDefintion = BlockDefintion{
-    transparency: false,
-    color: [0.5, 0.5, 0.5]
-};
\ No newline at end of file
@@ -0,0 +1,224 @@
+use crate::blocks::{CHUNK_HEIGHT, CHUNK_WIDTH};
+use crate::direction::AbsolouteDirection;
+use crate::render::voxel::{VOXEL_WIDTH, INVERSE_VOXEL_WIDTH};
+
+/*
+* Every kind of position in the world gets its own type so it
+* can't be mixed up with the others:
+* - WorldPos: metres, what the camera and physics use
+* - BlockPos: whole blocks, VOXEL_WIDTH metres wide
+* - ChunkId: whole chunks, CHUNK_WIDTH blocks wide
+* - LocalPos: a block inside of a chunk
+* Going from a finer type to a coarser one always floors, so
+* negative positions land in the right block and chunk.
+*/
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WorldPos{
+    pub x: f32,
+    pub y: f32,
+    pub z: f32
+}
+
+// the position of a block in the world, in blocks
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BlockPos{
+    pub x: i32,
+    pub y: i32,
+    pub z: i32
+}
+
+// picture it as coordinates on a map
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ChunkId{
+    pub x: i32,
+    pub z: i32
+}
+
+// a block inside of a chunk. always in bounds
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LocalPos{
+    pub x: usize,
+    pub y: usize,
+    pub z: usize
+}
+
+impl WorldPos{
+    pub fn new(x: f32, y: f32, z: f32) -> Self{
+        Self{ x, y, z }
+    }
+
+    // the block this point is inside of
+    pub fn to_block_pos(&self) -> BlockPos{
+        BlockPos{
+            x: (self.x * INVERSE_VOXEL_WIDTH).floor() as i32,
+            y: (self.y * INVERSE_VOXEL_WIDTH).floor() as i32,
+            z: (self.z * INVERSE_VOXEL_WIDTH).floor() as i32
+        }
+    }
+
+    // the last block a span ending at this point is inside of.
+    // a span that only reaches the side of a block stops short of it
+    pub fn to_last_block_pos(&self) -> BlockPos{
+        BlockPos{
+            x: (self.x * INVERSE_VOXEL_WIDTH).ceil() as i32 - 1,
+            y: (self.y * INVERSE_VOXEL_WIDTH).ceil() as i32 - 1,
+            z: (self.z * INVERSE_VOXEL_WIDTH).ceil() as i32 - 1
+        }
+    }
+
+    pub fn to_chunk_id(&self) -> ChunkId{
+        self.to_block_pos().to_chunk_id()
+    }
+}
+
+impl From<cgmath::Point3<f32>> for WorldPos{
+    fn from(p: cgmath::Point3<f32>) -> Self{
+        Self{ x: p.x, y: p.y, z: p.z }
+    }
+}
+
+impl From<WorldPos> for cgmath::Point3<f32>{
+    fn from(p: WorldPos) -> Self{
+        cgmath::Point3::new(p.x, p.y, p.z)
+    }
+}
+
+impl BlockPos{
+    pub fn new(x: i32, y: i32, z: i32) -> Self{
+        Self{ x, y, z }
+    }
+
+    pub fn from_local(chunk: ChunkId, local: LocalPos) -> Self{
+        Self{
+            x: chunk.x * CHUNK_WIDTH as i32 + local.x as i32,
+            y: local.y as i32,
+            z: chunk.z * CHUNK_WIDTH as i32 + local.z as i32
+        }
+    }
+
+    pub fn offset(&self, x: i32, y: i32, z: i32) -> Self{
+        Self{ x: self.x + x, y: self.y + y, z: self.z + z }
+    }
+
+    // the block touching this one on the given side
+    pub fn neighbor(&self, direction: AbsolouteDirection) -> Self{
+        let [x, y, z] = direction.offset();
+        self.offset(x, y, z)
+    }
+
+    // the corner of the block with the lowest x, y and z
+    pub fn to_world_pos(&self) -> WorldPos{
+        WorldPos{
+            x: self.x as f32 * VOXEL_WIDTH,
+            y: self.y as f32 * VOXEL_WIDTH,
+            z: self.z as f32 * VOXEL_WIDTH
+        }
+    }
+
+    pub fn to_chunk_id(&self) -> ChunkId{
+        ChunkId{
+            x: self.x.div_euclid(CHUNK_WIDTH as i32),
+            z: self.z.div_euclid(CHUNK_WIDTH as i32)
+        }
+    }
+
+    // None if the block is above or below the world
+    pub fn to_local(&self) -> Option<LocalPos>{
+        if self.y < 0 || self.y >= CHUNK_HEIGHT as i32{ return None; }
+        Some(LocalPos{
+            x: self.x.rem_euclid(CHUNK_WIDTH as i32) as usize,
+            y: self.y as usize,
+            z: self.z.rem_euclid(CHUNK_WIDTH as i32) as usize
+        })
+    }
+}
+
+impl ChunkId{
+    // the block in this chunk with the lowest x and z, at y = 0
+    pub fn to_block_pos(&self) -> BlockPos{
+        BlockPos{
+            x: self.x * CHUNK_WIDTH as i32,
+            y: 0,
+            z: self.z * CHUNK_WIDTH as i32
+        }
+    }
+
+    // north, south, east, west
+    pub fn neighbors(&self) -> [ChunkId; 4]{
+        [
+            ChunkId{ x: self.x, z: self.z + 1 },
+            ChunkId{ x: self.x, z: self.z - 1 },
+            ChunkId{ x: self.x + 1, z: self.z },
+            ChunkId{ x: self.x - 1, z: self.z }
+        ]
+    }
+}
+
+impl LocalPos{
+    // None if the position is outside of a chunk
+    pub fn new(x: usize, y: usize, z: usize) -> Option<Self>{
+        if x >= CHUNK_WIDTH || y >= CHUNK_HEIGHT || z >= CHUNK_WIDTH{ return None; }
+        Some(Self{ x, y, z })
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn world_to_block_floors(){
+        assert_eq!(WorldPos::new(0.0, 0.0, 0.0).to_block_pos(), BlockPos::new(0, 0, 0));
+        assert_eq!(WorldPos::new(0.24, 0.26, 1.0).to_block_pos(), BlockPos::new(0, 1, 4));
+        // just below zero is the block before zero, not block 0
+        assert_eq!(WorldPos::new(-0.01, -0.25, -0.26).to_block_pos(), BlockPos::new(-1, -1, -2));
+    }
+
+    #[test]
+    fn spans_end_before_the_side_they_reach(){
+        assert_eq!(WorldPos::new(0.25, 0.26, 1.0).to_last_block_pos(), BlockPos::new(0, 1, 3));
+        assert_eq!(WorldPos::new(0.0, -0.01, -0.25).to_last_block_pos(), BlockPos::new(-1, -1, -2));
+    }
+
+    #[test]
+    fn block_to_chunk_handles_negatives(){
+        assert_eq!(BlockPos::new(0, 5, 0).to_chunk_id(), ChunkId{ x: 0, z: 0 });
+        assert_eq!(BlockPos::new(15, 5, 16).to_chunk_id(), ChunkId{ x: 0, z: 1 });
+        assert_eq!(BlockPos::new(-1, 5, -16).to_chunk_id(), ChunkId{ x: -1, z: -1 });
+        assert_eq!(BlockPos::new(-17, 5, -15).to_chunk_id(), ChunkId{ x: -2, z: -1 });
+    }
+
+    #[test]
+    fn block_to_local_handles_negatives(){
+        assert_eq!(BlockPos::new(-1, 3, -16).to_local(), LocalPos::new(15, 3, 0));
+        assert_eq!(BlockPos::new(17, 127, -17).to_local(), LocalPos::new(1, 127, 15));
+        assert_eq!(BlockPos::new(0, -1, 0).to_local(), None);
+        assert_eq!(BlockPos::new(0, CHUNK_HEIGHT as i32, 0).to_local(), None);
+    }
+
+    #[test]
+    fn local_round_trips(){
+        for pos in [BlockPos::new(-33, 0, 7), BlockPos::new(12, 64, -1), BlockPos::new(-16, 127, 16)]{
+            let local = pos.to_local().unwrap();
+            assert_eq!(BlockPos::from_local(pos.to_chunk_id(), local), pos);
+        }
+    }
+
+    #[test]
+    fn local_pos_is_checked(){
+        assert!(LocalPos::new(15, 127, 15).is_some());
+        assert!(LocalPos::new(16, 0, 0).is_none());
+        assert!(LocalPos::new(0, CHUNK_HEIGHT, 0).is_none());
+    }
+
+    #[test]
+    fn chunk_and_world_agree(){
+        // the first block of chunk (-1, 2) starts 4 metres west of 0
+        let corner = ChunkId{ x: -1, z: 2 }.to_block_pos();
+        assert_eq!(corner, BlockPos::new(-16, 0, 32));
+        assert_eq!(corner.to_world_pos(), WorldPos::new(-4.0, 0.0, 8.0));
+        assert_eq!(WorldPos::new(-4.0, 0.0, 8.0).to_chunk_id(), ChunkId{ x: -1, z: 2 });
+        assert_eq!(WorldPos::new(-4.01, 0.0, 7.99).to_chunk_id(), ChunkId{ x: -2, z: 1 });
+    }
+}
\ No newline at end of file
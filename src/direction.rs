@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum AbsolouteDirection{
     North = 0,
     East = 1,
@@ -8,6 +8,31 @@ pub enum AbsolouteDirection{
     Down = 5 // if pictured on a map, points away from the viewer
 }
 
+impl AbsolouteDirection{
+    pub fn opposite(&self) -> Self{
+        match self{
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+            Self::Up => Self::Down,
+            Self::Down => Self::Up
+        }
+    }
+
+    // one block in this direction, as x y z
+    pub fn offset(&self) -> [i32; 3]{
+        match self{
+            Self::North => [0, 0, 1],
+            Self::East => [1, 0, 0],
+            Self::South => [0, 0, -1],
+            Self::West => [-1, 0, 0],
+            Self::Up => [0, 1, 0],
+            Self::Down => [0, -1, 0]
+        }
+    }
+}
+
 impl Into<i32> for AbsolouteDirection{
     fn into(self) -> i32 {
         match self{
@@ -21,6 +46,22 @@ impl Into<i32> for AbsolouteDirection{
     }
 }
 
+impl TryFrom<i32> for AbsolouteDirection{
+    type Error = ();
+
+    fn try_from(value: i32) -> Result<Self, ()>{
+        match value{
+            0 => Ok(Self::North),
+            1 => Ok(Self::East),
+            2 => Ok(Self::South),
+            3 => Ok(Self::West),
+            4 => Ok(Self::Up),
+            5 => Ok(Self::Down),
+            _ => Err(())
+        }
+    }
+}
+
 impl Into<f32> for AbsolouteDirection{
     fn into(self) -> f32 {
         match self{
@@ -25,7 +25,7 @@ impl EntityList{
 
     pub fn update(&mut self, world: &World, dt: f32){
         if self.player.physics_on{
-            world.check_col(&mut self.player)
+            world.simulate(&mut self.player, dt);
         }
     }
 }
\ No newline at end of file
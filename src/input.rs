@@ -1,4 +1,4 @@
-use winit::event::{WindowEvent, VirtualKeyCode, KeyboardInput, ElementState};
+use winit::event::{WindowEvent, VirtualKeyCode, KeyboardInput, ElementState, MouseButton, MouseScrollDelta};
 use hashbrown::HashMap;
 
 // returns the keyboard keys that i feel like being allowed
@@ -31,6 +31,16 @@ fn get_suppoerted_keys() -> Vec<VirtualKeyCode>{
         VirtualKeyCode::X,
         VirtualKeyCode::Y,
         VirtualKeyCode::Z,
+        // numbers
+        VirtualKeyCode::Key1,
+        VirtualKeyCode::Key2,
+        VirtualKeyCode::Key3,
+        VirtualKeyCode::Key4,
+        VirtualKeyCode::Key5,
+        VirtualKeyCode::Key6,
+        VirtualKeyCode::Key7,
+        VirtualKeyCode::Key8,
+        VirtualKeyCode::Key9,
         // arrow keys
         VirtualKeyCode::Up,
         VirtualKeyCode::Down,
@@ -45,7 +55,13 @@ pub struct InputHandler{
     key_states: HashMap<VirtualKeyCode, bool>,
     // match a certain event such as up wih a specific key
     key_events: HashMap<String, VirtualKeyCode>,
-    new_pressed: Vec<VirtualKeyCode>
+    new_pressed: Vec<VirtualKeyCode>,
+    // same as the keyboard, but for mouse buttons
+    mouse_states: HashMap<MouseButton, bool>,
+    mouse_events: HashMap<String, MouseButton>,
+    new_mouse_pressed: Vec<MouseButton>,
+    // lines scrolled since the last take_scroll, up is positive
+    scroll: f32
 }
 
 impl InputHandler{
@@ -72,11 +88,32 @@ impl InputHandler{
         key_events.insert("up".into(), VirtualKeyCode::Z);
         key_events.insert("toggle-physics".into(), VirtualKeyCode::Q);
         key_events.insert("toggle-debug".into(), VirtualKeyCode::L);
+        key_events.insert("pause".into(), VirtualKeyCode::P);
+        let number_keys = [
+            VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+            VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6,
+            VirtualKeyCode::Key7, VirtualKeyCode::Key8, VirtualKeyCode::Key9
+        ];
+        for (i, key) in number_keys.into_iter().enumerate(){
+            key_events.insert(format!("hotbar-{}", i + 1), key);
+        }
+
+        let mut mouse_states: HashMap<MouseButton, bool> = HashMap::new();
+        let mut mouse_events: HashMap<String, MouseButton> = HashMap::new();
+        mouse_events.insert("break".into(), MouseButton::Left);
+        mouse_events.insert("place".into(), MouseButton::Right);
+        for button in mouse_events.values(){
+            mouse_states.insert(*button, false);
+        }
 
         Self { 
             key_states: key_states,
             key_events: key_events,
-            new_pressed: Vec::new()
+            new_pressed: Vec::new(),
+            mouse_states,
+            mouse_events,
+            new_mouse_pressed: Vec::new(),
+            scroll: 0.0
          }
     }
 
@@ -92,6 +129,22 @@ impl InputHandler{
                 self.key_states.insert(*virtual_keycode, *state == ElementState::Pressed);
                 true
             },
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pressed = *state == ElementState::Pressed;
+                if pressed && !self.mouse_states.get(button).copied().unwrap_or(false){
+                    self.new_mouse_pressed.push(*button)
+                }
+                self.mouse_states.insert(*button, pressed);
+                true
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll += match delta{
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    // touchpads scroll in pixels, call it 20 to a line
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / 20.0
+                };
+                true
+            },
             _ => { false }
         }
     }
@@ -116,11 +169,24 @@ impl InputHandler{
     }
 
     pub fn check_new_event(&self, event: String) -> bool{
+        // events can be on a mouse button instead of a key
+        if let Some(button) = self.mouse_events.get(&event){
+            return self.new_mouse_pressed.contains(button);
+        }
         let key = self.key_events.get(&event).unwrap();
         self.new_pressed.contains(key)
     }
 
+    // whole lines scrolled since the last call. partial lines
+    // are kept for next time
+    pub fn take_scroll(&mut self) -> i32{
+        let lines = self.scroll.trunc();
+        self.scroll -= lines;
+        lines as i32
+    }
+
     pub fn flush_new_presses(&mut self){
-        self.new_pressed.clear()
+        self.new_pressed.clear();
+        self.new_mouse_pressed.clear()
     }
 }
\ No newline at end of file
@@ -0,0 +1,184 @@
+use std::io;
+
+use crate::blocks::AIR_ID;
+use crate::save::BlockIdMap;
+use crate::save::bytes::{ByteReader, ByteWriter, invalid_data};
+
+pub const HOTBAR_SIZE: usize = 9;
+// the slots past the hotbar, three rows of nine
+pub const STORAGE_SIZE: usize = 27;
+pub const INVENTORY_SIZE: usize = HOTBAR_SIZE + STORAGE_SIZE;
+pub const MAX_STACK: u8 = 64;
+
+// for now every item is a block, and an item's id is the
+// id of the block it places
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ItemStack{
+    pub item: usize,
+    pub count: u8
+}
+
+// the slots are the hotbar first, then the main storage
+#[derive(Clone)]
+pub struct Inventory{
+    slots: [Option<ItemStack>; INVENTORY_SIZE],
+    // always a hotbar slot
+    selected: usize
+}
+
+impl Inventory{
+    pub fn new() -> Self{
+        Self{ slots: [None; INVENTORY_SIZE], selected: 0 }
+    }
+
+    // fills stacks of the same item before starting new ones,
+    // and the hotbar before the storage. returns how many
+    // items didn't fit
+    pub fn add(&mut self, item: usize, mut count: u32) -> u32{
+        for stack in self.slots.iter_mut().flatten().filter(|s| s.item == item){
+            let moved = count.min((MAX_STACK - stack.count) as u32);
+            stack.count += moved as u8;
+            count -= moved;
+        }
+        for slot in self.slots.iter_mut().filter(|s| s.is_none()){
+            if count == 0{ break; }
+            let moved = count.min(MAX_STACK as u32);
+            *slot = Some(ItemStack{ item, count: moved as u8 });
+            count -= moved;
+        }
+        count
+    }
+
+    pub fn get(&self, slot: usize) -> Option<ItemStack>{
+        self.slots.get(slot).copied().flatten()
+    }
+
+    pub fn get_selected(&self) -> usize{
+        self.selected
+    }
+
+    // slots outside of the hotbar are ignored
+    pub fn select(&mut self, slot: usize){
+        if slot < HOTBAR_SIZE{
+            self.selected = slot;
+        }
+    }
+
+    // moves the selection along the hotbar, wrapping at the ends
+    pub fn scroll(&mut self, amount: i32){
+        self.selected = (self.selected as i32 + amount).rem_euclid(HOTBAR_SIZE as i32) as usize;
+    }
+
+    pub fn selected_item(&self) -> Option<usize>{
+        self.get(self.selected).map(|s| s.item)
+    }
+
+    // uses up one of the selected item
+    pub fn take_selected(&mut self) -> Option<usize>{
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        let item = stack.item;
+        stack.count -= 1;
+        if stack.count == 0{ *slot = None; }
+        Some(item)
+    }
+
+    // only filled slots are written, with save ids for the items
+    pub fn write(&self, w: &mut ByteWriter, ids: &BlockIdMap){
+        w.write_u8(self.selected as u8);
+        w.write_u8(self.slots.iter().flatten().count() as u8);
+        for (slot, stack) in self.slots.iter().enumerate(){
+            if let Some(stack) = stack{
+                w.write_u8(slot as u8);
+                w.write_u32(ids.save_id(stack.item));
+                w.write_u8(stack.count);
+            }
+        }
+    }
+
+    pub fn read(r: &mut ByteReader, ids: &BlockIdMap) -> io::Result<Self>{
+        let mut inventory = Self::new();
+        inventory.select(r.read_u8()? as usize);
+        for _ in 0..r.read_u8()?{
+            let slot = r.read_u8()? as usize;
+            let item = ids.runtime_id(r.read_u32()?)?;
+            let count = r.read_u8()?;
+            if slot >= INVENTORY_SIZE || count == 0 || count > MAX_STACK{
+                return Err(invalid_data("inventory has a bad item stack"));
+            }
+            // the block was removed from the game, so the items go too
+            if item == AIR_ID{ continue; }
+            inventory.slots[slot] = Some(ItemStack{ item, count });
+        }
+        Ok(inventory)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::{BlockRegistry, BLOCKS, DIRT_ID, STONE_ID};
+
+    #[test]
+    fn stacks_fill_before_new_slots(){
+        let mut inventory = Inventory::new();
+        assert_eq!(inventory.add(*STONE_ID, 10), 0);
+        assert_eq!(inventory.add(*DIRT_ID, 1), 0);
+        assert_eq!(inventory.add(*STONE_ID, 60), 0);
+        assert_eq!(inventory.get(0), Some(ItemStack{ item: *STONE_ID, count: MAX_STACK }));
+        assert_eq!(inventory.get(1), Some(ItemStack{ item: *DIRT_ID, count: 1 }));
+        assert_eq!(inventory.get(2), Some(ItemStack{ item: *STONE_ID, count: 6 }));
+        // a full inventory hands back what didn't fit
+        let room = (INVENTORY_SIZE as u32 - 3) * MAX_STACK as u32 + (MAX_STACK - 6) as u32;
+        assert_eq!(inventory.add(*STONE_ID, room + 5), 5);
+    }
+
+    #[test]
+    fn selection_stays_on_the_hotbar(){
+        let mut inventory = Inventory::new();
+        inventory.add(*DIRT_ID, 1);
+        assert_eq!(inventory.take_selected(), Some(*DIRT_ID));
+        assert_eq!(inventory.take_selected(), None);
+        inventory.scroll(-1);
+        assert_eq!(inventory.get_selected(), HOTBAR_SIZE - 1);
+        inventory.scroll(2);
+        assert_eq!(inventory.get_selected(), 1);
+        inventory.select(HOTBAR_SIZE);
+        assert_eq!(inventory.get_selected(), 1);
+    }
+
+    #[test]
+    fn inventory_round_trips(){
+        let ids = BlockIdMap::new(Vec::new(), &BLOCKS);
+        let mut inventory = Inventory::new();
+        inventory.add(*STONE_ID, 100);
+        inventory.add(*DIRT_ID, 3);
+        inventory.select(4);
+        let mut w = ByteWriter::new();
+        inventory.write(&mut w, &ids);
+        let data = w.into_inner();
+        let read = Inventory::read(&mut ByteReader::new(&data), &ids).unwrap();
+        assert_eq!(read.get_selected(), 4);
+        for slot in 0..INVENTORY_SIZE{
+            assert_eq!(read.get(slot), inventory.get(slot));
+        }
+    }
+
+    #[test]
+    fn removed_items_are_dropped(){
+        let old = BlockRegistry::parse("[stone]\n[marble]\n").unwrap();
+        let ids = BlockIdMap::new(Vec::new(), &old);
+        let mut inventory = Inventory::new();
+        inventory.add(old.expect_id("marble"), 5);
+        inventory.add(old.expect_id("stone"), 2);
+        let mut w = ByteWriter::new();
+        inventory.write(&mut w, &ids);
+        let data = w.into_inner();
+
+        let new = BlockRegistry::parse("[stone]\n").unwrap();
+        let ids = BlockIdMap::new(ids.names().to_vec(), &new);
+        let read = Inventory::read(&mut ByteReader::new(&data), &ids).unwrap();
+        assert_eq!(read.get(0), None);
+        assert_eq!(read.get(1), Some(ItemStack{ item: new.expect_id("stone"), count: 2 }));
+    }
+}
\ No newline at end of file
@@ -13,14 +13,21 @@ use render::{
     voxel::Voxel,
     mesh::MeshTrait
 };
+use physics::PhysicsObject;
 
 mod blocks;
+mod coords;
 mod input;
+mod inventory;
 mod direction;
 mod entity;
 mod player;
 mod physics;
+mod raycast;
+mod ticking;
 mod world;
+mod save;
+mod worldgen;
 mod time_keep;
 use time_keep::TimeKeep;
 
@@ -41,12 +48,38 @@ pub async fn run() {
     let mut time_keeper = TimeKeep::new();
     let mut debug = false;
 
-    let mut world = world::World::new(ws.get_device_ref());
+    // world setup. ex: --save saves/other --preset amplified --seed 42
+    let args: Vec<String> = std::env::args().collect();
+    let save_dir = args.iter()
+        .position(|a| a == "--save")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .unwrap_or("saves/world".into());
+    let settings = worldgen::WorldGenSettings::from_args(&args).expect("Invalid world generation arguments");
+    let save = save::WorldSave::open(save_dir, settings).expect("Failed to open world save");
+    let settings = save.get_settings().clone();
+    let mut world = world::World::new(world::DEFAULT_RENDER_DISTANCE, settings, Some(save));
+    // ex: --tick-speed 0 stops blocks from changing on their own
+    if let Some(speed) = args.iter().position(|a| a == "--tick-speed").and_then(|i| args.get(i + 1)){
+        world.set_random_tick_speed(speed.parse().expect("Invalid tick speed"));
+    }
+    // ex: --time-scale 0.5 runs everything at half speed
+    if let Some(scale) = args.iter().position(|a| a == "--time-scale").and_then(|i| args.get(i + 1)){
+        time_keeper.set_time_scale(scale.parse().expect("Invalid time scale"));
+    }
+    let mut chunk_meshes = render::voxel::ChunkMeshes::new();
     let mut el = entity::EntityList::new(window.inner_size().width, window.inner_size().height);
 
     // player and input
     let mut input_handler = input::InputHandler::new();
     let player_ref = el.get_player_mut();
+    if let Some(saved) = world.load_player(){
+        player_ref.load_save(saved);
+    }
+    // ex: --step-height 0 stops the player walking up ledges
+    if let Some(height) = args.iter().position(|a| a == "--step-height").and_then(|i| args.get(i + 1)){
+        player_ref.get_data_mut().step_height = height.parse().expect("Invalid step height");
+    }
     let camera_ref = player_ref.get_camera_ref_mut();
     let camera_uniform = camera_ref.create_uniform();
 
@@ -54,11 +87,19 @@ pub async fn run() {
     //ws.register_shader::<TutorialVertex>("tutorial".into(), include_str!("assets/tutorial_shader.wgsl"), Vec::new(), &[]);
     ws.register_shader::<TutorialVertex>("mesh".into(), include_str!("assets/mesh_shader.wgsl"), 
         vec![camera_uniform.get_bind_group(ws.get_device_ref())], 
-        &[&render::camera::CameraUniform::get_bind_group_layout(ws.get_device_ref())]
+        &[&render::camera::CameraUniform::get_bind_group_layout(ws.get_device_ref())],
+        false
     );
     ws.register_shader::<VoxelVertex>("voxel".into(), include_str!("assets/voxel_shader.wgsl"), 
         vec![camera_uniform.get_bind_group(ws.get_device_ref())], 
-        &[&render::camera::CameraUniform::get_bind_group_layout(ws.get_device_ref())]
+        &[&render::camera::CameraUniform::get_bind_group_layout(ws.get_device_ref())],
+        false
+    );
+    // same shader, but blended and drawn after everything solid
+    ws.register_shader::<VoxelVertex>("voxel_translucent".into(), include_str!("assets/voxel_shader.wgsl"), 
+        vec![camera_uniform.get_bind_group(ws.get_device_ref())], 
+        &[&render::camera::CameraUniform::get_bind_group_layout(ws.get_device_ref())],
+        true
     );
 
     event_loop.run(move |event, _, control_flow| match event {
@@ -76,7 +117,11 @@ pub async fn run() {
                             ..
                         },
                       ..
-                } => *control_flow = event_loop::ControlFlow::Exit,
+                } => {
+                    world.save_all().expect("Failed to save world");
+                    world.save_player(&el.get_player_mut().to_save()).expect("Failed to save player");
+                    *control_flow = event_loop::ControlFlow::Exit
+                },
                 event::WindowEvent::Resized(physical_size) => {
                     ws.resize(*physical_size);
                 },
@@ -89,14 +134,39 @@ pub async fn run() {
         event::Event::RedrawRequested(window_id) if window_id == window.id() => {
             // update camera uniform each frame
             let camera_ref = el.get_player_mut().get_camera_ref_mut();
-            let new_camera_bind_group = camera_ref.create_uniform().get_bind_group(ws.get_device_ref_mut());
-            ws.update_shader_bind_group(
-                "voxel".into(),
-                0, 
-                new_camera_bind_group
-            );
-
-            match ws.render::<render::voxel::VoxelVertex, render::voxel::VoxelMesh>(world.get_chunk_meshes(), debug) {
+            let camera_uniform = camera_ref.create_uniform();
+            for shader in ["voxel", "voxel_translucent"]{
+                let new_camera_bind_group = camera_uniform.get_bind_group(ws.get_device_ref_mut());
+                ws.update_shader_bind_group(
+                    shader.into(),
+                    0, 
+                    new_camera_bind_group
+                );
+            }
+            let camera_pos = coords::WorldPos::from(el.get_player_mut().get_data().position);
+
+            let debug_text = if debug{
+                let block = coords::WorldPos::from(el.get_player_mut().get_data().position).to_block_pos();
+                let ray = raycast::Ray::from_camera(el.get_player_mut().get_camera_ref_mut());
+                let target = match world.raycast(&ray, &raycast::RaycastSettings::default()){
+                    Some(hit) => format!("{} {} {} ({:?})", hit.block.x, hit.block.y, hit.block.z, hit.face),
+                    None => "none".into()
+                };
+                let inventory = el.get_player_mut().get_inventory_mut();
+                let slot = inventory.get_selected();
+                let holding = match inventory.get(slot){
+                    Some(stack) => format!("{} x{}", blocks::BLOCKS.get(stack.item).name, stack.count),
+                    None => "nothing".into()
+                };
+                let time = if time_keeper.is_paused(){ "paused".into() }else{ format!("x{}", time_keeper.get_time_scale()) };
+                Some(format!("Debug\nBlock: {} {} {}\nBiome: {:?}\nTarget: {}\nSlot {}: {}\nTime: {}", block.x, block.y, block.z, world.biome_at(block.x, block.z), target, slot + 1, holding, time))
+            }else{ None };
+
+            match ws.render::<render::voxel::VoxelVertex, render::voxel::VoxelMesh>(
+                chunk_meshes.get_meshes(),
+                chunk_meshes.get_translucent_meshes(camera_pos),
+                debug_text.as_deref()
+            ) {
                 Ok(_) => {}
                 // Reconfigure the surface if lost
                 Err(wgpu::SurfaceError::Lost) => ws.resize(ws.size),
@@ -110,6 +180,7 @@ pub async fn run() {
             let dt = time_keeper.update_and_get_dt();
             println!("dt: {}", dt);
             println!("fps: {}", 1.0 / dt);
+            let ticks = time_keeper.advance(dt);
             // movement
             let forward = input_handler.get_key_event("forward".into());
             let backward = input_handler.get_key_event("backward".into());
@@ -125,25 +196,65 @@ pub async fn run() {
             // funnnnnn stuffff
             let toggle_physics = input_handler.check_new_event("toggle-physics".into());
             let toggle_debug = input_handler.check_new_event("toggle-debug".into());
+            let toggle_pause = input_handler.check_new_event("pause".into());
+            // editing the world
+            let break_block = input_handler.check_new_event("break".into());
+            let place_block = input_handler.check_new_event("place".into());
+            // picking from the hotbar
+            let hotbar_key = (1..=inventory::HOTBAR_SIZE).find(|i| input_handler.check_new_event(format!("hotbar-{}", i)));
+            let scroll = input_handler.take_scroll();
 
             input_handler.flush_new_presses();
 
             let player_ref = el.get_player_mut();
             // fun toggles
             if toggle_physics{
-                player_ref.physics_on = !player_ref.physics_on;
+                player_ref.toggle_physics();
             }
             if toggle_debug{
                 debug = !debug;
             }
-            // player input
-            player_ref.handle_input(
-                [ forward as i32 + -1*backward as i32, right as i32 + -1*left as i32, up as i32 + -1*down as i32],
-                [ look_right as i32 + -1*look_left as i32, look_up as i32 + -1*look_down as i32],
-                dt
-            );
-
-            el.update(&world, dt);
+            if toggle_pause{
+                time_keeper.toggle_pause();
+            }
+            if let Some(slot) = hotbar_key{
+                player_ref.get_inventory_mut().select(slot - 1);
+            }
+            // scrolling down moves right along the hotbar
+            player_ref.get_inventory_mut().scroll(-scroll);
+            // the world can't be changed while time is stopped
+            if break_block && !time_keeper.is_paused(){
+                player_ref.break_block(&mut world);
+            }
+            if place_block && !time_keeper.is_paused(){
+                player_ref.place_block(&mut world);
+            }
+
+            let movement = [ forward as i32 + -1*backward as i32, right as i32 + -1*left as i32, up as i32 + -1*down as i32];
+            let rotation = [ look_right as i32 + -1*look_left as i32, look_up as i32 + -1*look_down as i32];
+            // looking around and flying follow the frame rate,
+            // even while paused
+            player_ref.look(rotation, dt);
+            if !player_ref.physics_on{
+                player_ref.handle_input(movement, dt);
+            }
+            // the simulation runs at a fixed rate however fast
+            // frames are drawn
+            for _ in 0..ticks{
+                let player_ref = el.get_player_mut();
+                player_ref.start_tick();
+                if player_ref.physics_on{
+                    player_ref.handle_input(movement, time_keep::TICK_DT);
+                }
+                el.update(&world, time_keep::TICK_DT);
+                world.tick();
+            }
+            el.get_player_mut().interpolate_camera(time_keeper.get_alpha());
+
+            // stream chunks around the player
+            let player_pos = el.get_player_mut().get_data().position;
+            world.update(coords::WorldPos::from(player_pos).to_chunk_id());
+            chunk_meshes.update(&mut world, ws.get_device_ref());
 
             // RedrawRequested will only trigger once, unless we manually
             // request it.
@@ -1,8 +1,19 @@
-use cgmath;
+use std::ops::RangeInclusive;
 
-use crate::{blocks::{Chunk, BLOCK_ARRAY}, render::voxel::VOXEL_WIDTH};
+use cgmath::{self, InnerSpace};
 
-pub const GRAVITY_FORCE: cgmath::Vector3<f32> = cgmath::Vector3{ x: 0.0, y: -1.0, z: 0.0 };
+use crate::{blocks::{Chunk, BLOCKS}, coords::{BlockPos, WorldPos}, render::voxel::VOXEL_WIDTH};
+
+// in metres a second a second. everything falls the same
+// however heavy it is
+pub const GRAVITY_FORCE: cgmath::Vector3<f32> = cgmath::Vector3{ x: 0.0, y: -9.8, z: 0.0 };
+// in metres a second. falling for longer doesn't go any faster
+pub const MAX_FALL_SPEED: f32 = 20.0;
+// how close a box gets to a block it runs into. keeps it off of
+// the border between cells, where rounding could put it inside
+const SKIN: f32 = 0.001;
+// how tall a ledge things walk up onto without jumping
+pub const DEFAULT_STEP_HEIGHT: f32 = VOXEL_WIDTH;
 
 pub trait PhysicsObject{
     fn get_data(&self) -> &PhysicsData;
@@ -11,6 +22,7 @@ pub trait PhysicsObject{
 }
 
 // 3d version of a hitbox
+#[derive(Clone)]
 pub struct PhysicsData{
     pub position: cgmath::Point3<f32>,
     pub width: f32,
@@ -18,13 +30,303 @@ pub struct PhysicsData{
     pub depth: f32,
     // only rotate perpindicular to y-plane
     pub rotation: cgmath::Rad<f32>,
-    // actually just speed, don't worry about it
+    // actually just speed, don't worry about it.
+    // in metres a second
     pub force: cgmath::Vector3<f32>,
-    pub mass: f32
+    pub mass: f32,
+    // whether the last move ended standing on something
+    pub on_ground: bool,
+    // in metres. 0 never steps up
+    pub step_height: f32,
+    // won't walk off of edges while on the ground
+    pub sneaking: bool,
+    // inside of a climbable block, so it doesn't fall
+    pub climbing: bool
 }
 
 impl PhysicsData{
     pub fn r#move(&mut self, movement: cgmath::Vector3<f32>){
         self.position += movement;
     }
+
+    // the corners of the hitbox. position is its center
+    pub fn get_bounds(&self) -> (cgmath::Point3<f32>, cgmath::Point3<f32>){
+        let half = cgmath::Vector3::new(self.width, self.height, self.depth) / 2.0;
+        (self.position - half, self.position + half)
+    }
+
+    // whether the hitbox is partly inside of a block's cell.
+    // only touching the side of the cell doesn't count
+    pub fn overlaps_block(&self, block: BlockPos) -> bool{
+        let (min, max) = self.get_bounds();
+        let cell_min = block.to_world_pos();
+        let cell_max = block.offset(1, 1, 1).to_world_pos();
+        min.x < cell_max.x && max.x > cell_min.x
+            && min.y < cell_max.y && max.y > cell_min.y
+            && min.z < cell_max.z && max.z > cell_min.z
+    }
+
+    // speeds the box up for dt seconds of falling
+    pub fn apply_gravity(&mut self, dt: f32){
+        self.force += GRAVITY_FORCE * dt;
+        self.force.y = self.force.y.max(-MAX_FALL_SPEED);
+    }
+
+    // stops the box on the axes a move was blocked on
+    pub fn apply_collision(&mut self, collision: Collision){
+        if collision.x{ self.force.x = 0.0; }
+        if collision.y{ self.force.y = 0.0; }
+        if collision.z{ self.force.z = 0.0; }
+        self.on_ground = collision.ground;
+    }
+}
+
+// which axes a move was stopped on
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Collision{
+    pub x: bool,
+    pub y: bool,
+    pub z: bool,
+    // stopped while moving down, so it's standing on something
+    pub ground: bool
+}
+
+// the cells a box from min to max is inside of, on each axis.
+// cells it only touches the side of don't count
+fn cells_in(min: WorldPos, max: WorldPos) -> [RangeInclusive<i32>; 3]{
+    let (first, last) = (min.to_block_pos(), max.to_last_block_pos());
+    [first.x..=last.x, first.y..=last.y, first.z..=last.z]
+}
+
+// the coordinate of a block along an axis, 0 is x
+fn along(pos: BlockPos, axis: usize) -> i32{
+    [pos.x, pos.y, pos.z][axis]
+}
+
+// whether any cell the box is partly inside of passes check
+pub fn any_overlapping<F: Fn(BlockPos) -> bool>(data: &PhysicsData, check: F) -> bool{
+    let (min, max) = data.get_bounds();
+    let [xs, ys, zs] = cells_in(min.into(), max.into());
+    xs.into_iter().any(|x| {
+        ys.clone().any(|y| {
+            zs.clone().any(|z| check(BlockPos::new(x, y, z)))
+        })
+    })
+}
+
+// whether there's something solid right under the box if it
+// were at position
+fn has_ground_under<F: Fn(BlockPos) -> bool>(data: &PhysicsData, position: cgmath::Point3<f32>, is_solid: F) -> bool{
+    let half = cgmath::Vector3::new(data.width, 0.0, data.depth) / 2.0;
+    let feet = position - cgmath::Vector3::new(0.0, data.height / 2.0 + SKIN * 2.0, 0.0);
+    let below = WorldPos::from(feet).to_block_pos().y;
+    let [xs, _, zs] = cells_in((feet - half).into(), (feet + half).into());
+    xs.into_iter().any(|x| {
+        zs.clone().any(|z| is_solid(BlockPos::new(x, below, z)))
+    })
+}
+
+// takes away the parts of a sneaking box's movement that would
+// walk it off of what it's standing on. x and z are checked on
+// their own so it can still slide along the edge
+pub fn guard_edges<F: Fn(BlockPos) -> bool>(data: &PhysicsData, mut movement: cgmath::Vector3<f32>, is_solid: F) -> cgmath::Vector3<f32>{
+    if !data.sneaking || !data.on_ground{ return movement; }
+    let moved = |x: f32, z: f32| has_ground_under(data, data.position + cgmath::Vector3::new(x, 0.0, z), &is_solid);
+    if !moved(movement.x, 0.0){ movement.x = 0.0; }
+    if !moved(movement.x, movement.z){ movement.z = 0.0; }
+    movement
+}
+
+// like sweep, but a box on the ground that walks into a ledge
+// no taller than its step height is lifted up onto it
+pub fn sweep_with_step<F: Fn(BlockPos) -> bool>(data: &PhysicsData, movement: cgmath::Vector3<f32>, is_solid: F) -> (cgmath::Point3<f32>, Collision){
+    let (position, collision) = sweep(data, movement, &is_solid);
+    if data.step_height <= 0.0 || !data.on_ground || !(collision.x || collision.z){
+        return (position, collision);
+    }
+    // try the move again from up high, then drop back down
+    let mut probe = data.clone();
+    let (raised, _) = sweep(&probe, cgmath::Vector3::new(0.0, data.step_height, 0.0), &is_solid);
+    probe.position = raised;
+    let (across, stepped) = sweep(&probe, cgmath::Vector3::new(movement.x, 0.0, movement.z), &is_solid);
+    probe.position = across;
+    let drop = raised.y - data.position.y - movement.y.min(0.0);
+    let (landed, fell) = sweep(&probe, cgmath::Vector3::new(0.0, -drop, 0.0), &is_solid);
+    // only worth it if it got further, and onto something
+    let flat = |p: cgmath::Point3<f32>| cgmath::Vector2::new(p.x - data.position.x, p.z - data.position.z).magnitude2();
+    if !fell.ground || flat(landed) <= flat(position){
+        return (position, collision);
+    }
+    (landed, Collision{ x: stepped.x, y: true, z: stepped.z, ground: true })
+}
+
+// moves a hitbox one axis at a time, y first, stopping it at the
+// first solid block it runs into on each axis. blocks are looked
+// up with is_solid so this doesn't need a world to work. returns
+// where the box ended up
+pub fn sweep<F: Fn(BlockPos) -> bool>(data: &PhysicsData, movement: cgmath::Vector3<f32>, is_solid: F) -> (cgmath::Point3<f32>, Collision){
+    let half = [data.width / 2.0, data.height / 2.0, data.depth / 2.0];
+    let mut pos = [data.position.x, data.position.y, data.position.z];
+    let delta = [movement.x, movement.y, movement.z];
+    let mut hit = [false; 3];
+    let point = |p: [f32; 3]| WorldPos::new(p[0], p[1], p[2]);
+    for axis in [1, 0, 2]{
+        let d = delta[axis];
+        if d == 0.0{ continue; }
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let min: [f32; 3] = std::array::from_fn(|i| pos[i] - half[i]);
+        let max: [f32; 3] = std::array::from_fn(|i| pos[i] + half[i]);
+        // the side of the box moving into new cells
+        let lead = if d > 0.0{ max } else{ min };
+        let mut target = lead;
+        target[axis] += d;
+        // the layers of cells the side passes into, closest first
+        let layers: Vec<i32> = if d > 0.0{
+            (along(point(lead).to_last_block_pos(), axis) + 1..=along(point(target).to_last_block_pos(), axis)).collect()
+        }else{
+            (along(point(target).to_block_pos(), axis)..=along(point(lead).to_block_pos(), axis) - 1).rev().collect()
+        };
+        let cells = cells_in(point(min), point(max));
+        let blocked = layers.into_iter().find(|layer| {
+            cells[a].clone().any(|i| {
+                cells[b].clone().any(|j| {
+                    let mut cell = [0; 3];
+                    cell[axis] = *layer;
+                    cell[a] = i;
+                    cell[b] = j;
+                    is_solid(BlockPos::new(cell[0], cell[1], cell[2]))
+                })
+            })
+        });
+        // where the side of a block in the layer is
+        let side = |layer: i32| {
+            let mut cell = [0; 3];
+            cell[axis] = layer;
+            let corner = BlockPos::new(cell[0], cell[1], cell[2]).to_world_pos();
+            [corner.x, corner.y, corner.z][axis]
+        };
+        pos[axis] = match blocked{
+            Some(layer) if d > 0.0 => side(layer) - half[axis] - SKIN,
+            Some(layer) => side(layer + 1) + half[axis] + SKIN,
+            None => pos[axis] + d
+        };
+        hit[axis] = blocked.is_some();
+    }
+    let collision = Collision{ x: hit[0], y: hit[1], z: hit[2], ground: hit[1] && delta[1] < 0.0 };
+    (cgmath::Point3::new(pos[0], pos[1], pos[2]), collision)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // a block wide and two tall
+    fn hitbox(x: f32, y: f32, z: f32) -> PhysicsData{
+        PhysicsData{
+            position: cgmath::Point3::new(x, y, z),
+            width: VOXEL_WIDTH,
+            height: VOXEL_WIDTH * 2.0,
+            depth: VOXEL_WIDTH,
+            rotation: cgmath::Rad(0.0),
+            force: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            mass: 1.0,
+            on_ground: false,
+            step_height: DEFAULT_STEP_HEIGHT,
+            sneaking: false,
+            climbing: false
+        }
+    }
+
+    // solid below y = 0, with a wall at x = 2
+    fn floor_and_wall(pos: BlockPos) -> bool{
+        pos.y < 0 || pos.x == 2
+    }
+
+    #[test]
+    fn hitbox_overlaps_blocks_it_is_inside(){
+        // centered on the corner of four blocks
+        let pd = hitbox(0.0, 0.25, 0.0);
+        assert!(pd.overlaps_block(BlockPos::new(0, 0, 0)));
+        assert!(pd.overlaps_block(BlockPos::new(-1, 1, -1)));
+        // the feet only touch the top of the block below
+        assert!(!pd.overlaps_block(BlockPos::new(0, -1, 0)));
+        assert!(!pd.overlaps_block(BlockPos::new(1, 0, 0)));
+    }
+
+    #[test]
+    fn boxes_stop_on_the_floor(){
+        let pd = hitbox(0.1, 0.3, 0.1);
+        let (pos, collision) = sweep(&pd, cgmath::Vector3::new(0.0, -1.0, 0.0), floor_and_wall);
+        assert!(collision.y && collision.ground && !collision.x);
+        assert!((pos.y - (0.25 + SKIN)).abs() < 0.0001);
+        // moving along the floor doesn't catch on it
+        let pd = hitbox(0.1, pos.y, 0.1);
+        let (pos, collision) = sweep(&pd, cgmath::Vector3::new(0.2, 0.0, -3.0), floor_and_wall);
+        assert_eq!(collision, Collision::default());
+        assert!((pos.x - 0.3).abs() < 0.0001 && (pos.z + 2.9).abs() < 0.0001);
+    }
+
+    #[test]
+    fn walls_only_stop_their_axis(){
+        // sliding diagonally into the wall keeps the z movement
+        let pd = hitbox(0.1, 0.25 + SKIN, 0.1);
+        let (pos, collision) = sweep(&pd, cgmath::Vector3::new(1.0, 0.0, 0.5), floor_and_wall);
+        assert!(collision.x && !collision.z);
+        assert!((pos.x - (0.5 - 0.125 - SKIN)).abs() < 0.0001);
+        assert!((pos.z - 0.6).abs() < 0.0001);
+        // a fast move can't skip through the wall
+        let (pos, collision) = sweep(&pd, cgmath::Vector3::new(20.0, 0.0, 0.0), floor_and_wall);
+        assert!(collision.x && pos.x < 0.5);
+    }
+
+    #[test]
+    fn falling_is_capped_until_landing(){
+        let mut pd = hitbox(0.1, 0.3, 0.1);
+        for _ in 0..100{ pd.apply_gravity(0.1); }
+        assert_eq!(pd.force.y, -MAX_FALL_SPEED);
+        let (_, collision) = sweep(&pd, pd.force * 0.1, floor_and_wall);
+        pd.apply_collision(collision);
+        assert!(pd.on_ground);
+        assert_eq!(pd.force.y, 0.0);
+        // hitting a ceiling isn't standing on it
+        let ceiling = |pos: BlockPos| pos.y > 2;
+        let (_, collision) = sweep(&pd, cgmath::Vector3::new(0.0, 1.0, 0.0), ceiling);
+        assert!(collision.y && !collision.ground);
+    }
+
+    #[test]
+    fn steps_up_low_ledges_only(){
+        // a one block ledge past x = 2, then a wall two blocks taller past x = 4
+        let steps = |pos: BlockPos| pos.y < 0 || (pos.x >= 2 && pos.y < 1) || (pos.x >= 4 && pos.y < 3);
+        let mut pd = hitbox(0.375, 0.25 + SKIN, 0.1);
+        pd.on_ground = true;
+        let (pos, collision) = sweep_with_step(&pd, cgmath::Vector3::new(0.1, -0.01, 0.0), steps);
+        assert!(collision.ground && !collision.x);
+        assert!((pos.x - 0.475).abs() < 0.0001 && (pos.y - (0.5 + SKIN)).abs() < 0.0001);
+        pd.position = cgmath::Point3::new(0.875, 0.5 + SKIN, 0.1);
+        let (pos, collision) = sweep_with_step(&pd, cgmath::Vector3::new(0.1, -0.01, 0.0), steps);
+        assert!(collision.x && pos.x < 0.875 + SKIN);
+        // nothing is stepped up while in the air
+        pd.on_ground = false;
+        pd.position = cgmath::Point3::new(0.375, 0.25 + SKIN, 0.1);
+        let (_, collision) = sweep_with_step(&pd, cgmath::Vector3::new(0.1, -0.01, 0.0), steps);
+        assert!(collision.x);
+    }
+
+    #[test]
+    fn sneaking_stops_at_edges(){
+        // a platform from x = 0 to x = 2 (in blocks)
+        let platform = |pos: BlockPos| pos.y < 0 && (0..2).contains(&pos.x);
+        let mut pd = hitbox(0.3, 0.25 + SKIN, 0.1);
+        pd.on_ground = true;
+        pd.sneaking = true;
+        let movement = guard_edges(&pd, cgmath::Vector3::new(0.5, 0.0, 0.2), platform);
+        assert_eq!(movement, cgmath::Vector3::new(0.0, 0.0, 0.2));
+        // a little way is fine while something is still under it
+        let movement = guard_edges(&pd, cgmath::Vector3::new(0.1, 0.0, 0.0), platform);
+        assert_eq!(movement.x, 0.1);
+        pd.sneaking = false;
+        let movement = guard_edges(&pd, cgmath::Vector3::new(0.5, 0.0, 0.0), platform);
+        assert_eq!(movement.x, 0.5);
+    }
 }
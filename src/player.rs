@@ -1,13 +1,32 @@
 use cgmath::InnerSpace;
-use crate::{render::{camera::Camera, voxel::VOXEL_WIDTH}, physics::{PhysicsData, PhysicsObject}, blocks::CHUNK_WIDTH};
+use crate::{render::{camera::Camera, voxel::VOXEL_WIDTH}, physics::{PhysicsData, PhysicsObject, DEFAULT_STEP_HEIGHT}, blocks::CHUNK_WIDTH};
+use crate::blocks::{block_of, BlockState, Half, AIR_ID, BLOCKS};
+use crate::coords::BlockPos;
+use crate::direction::AbsolouteDirection;
+use crate::inventory::Inventory;
+use crate::raycast::{Ray, RaycastSettings};
+use crate::save::PlayerSave;
+use crate::world::World;
 use std::f64::consts::FRAC_PI_8;
 
+// how much slower sneaking is than walking
+const SNEAK_SPEED: f32 = 0.3;
+
 pub struct Player{
     camera: Camera,
     position: cgmath::Point3<f32>,
     speed: f32,
     rotation_speed: f32,
+    // how fast the player leaves the ground, in metres a
+    // second. enough to get up a block and a half
+    jump_speed: f32,
+    // up and down ladders, in metres a second
+    climb_speed: f32,
     pd: PhysicsData,
+    // where the player was before the last tick moved them,
+    // so the camera can be drawn between ticks
+    last_position: cgmath::Point3<f32>,
+    inventory: Inventory,
     pub physics_on: bool
 }
 
@@ -21,24 +40,44 @@ impl Player{
             mass: 10.0,
             position: position,
             rotation: cgmath::Rad(0.0),
-            force: cgmath::Vector3 { x: 0.0, y: 0.0, z: 0.0 }
+            force: cgmath::Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+            on_ground: false,
+            step_height: DEFAULT_STEP_HEIGHT,
+            sneaking: false,
+            climbing: false
         };
         Self{
             camera: Camera::new(position, 0.0, 0.0, scrn_height, scrn_height),
             position: position,
             speed: 5.0,
             rotation_speed: (FRAC_PI_8 / 4.0f64) as f32 * 10.0,
+            jump_speed: 2.8,
+            climb_speed: 2.0,
             pd,
+            last_position: position,
+            inventory: Inventory::new(),
             physics_on: false
         }
     }
 
-    pub fn handle_input(&mut self, movement: [i32; 3], rotation: [i32; 2], dt: f32){
-        let forward = movement[0] as f32 * self.speed * dt;
-        let strafe = movement[1] as f32 * self.speed * dt;
+    // turns the camera. done every frame so looking around stays
+    // smooth, and works while the game is paused
+    pub fn look(&mut self, rotation: [i32; 2], dt: f32){
         let dyaw = rotation[0] as f32 * self.rotation_speed * dt;
         let dpitch = rotation[1] as f32 * self.rotation_speed * dt;
-        let fly = movement[2] as f32 * dt * self.speed;
+        self.camera.rotate(dyaw, dpitch);
+    }
+
+    // with physics on this only sets how fast the player is
+    // going, and should be called once a tick. the world moves
+    // them when it checks for collisions. going up jumps, but
+    // only off of the ground. going down sneaks. on a ladder
+    // they climb it instead.
+    // flying moves the player straight away, once a frame
+    pub fn handle_input(&mut self, movement: [i32; 3], dt: f32){
+        let forward = movement[0] as f32 * self.speed;
+        let strafe = movement[1] as f32 * self.speed;
+        let fly = movement[2] as f32 * self.speed;
         
         let (yaw_sin, yaw_cos) = self.camera.yaw.0.sin_cos();
         let forward_dir = cgmath::Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
@@ -47,17 +86,108 @@ impl Player{
         let up_down = cgmath::Vector3::new(0.0, fly, 0.0);
         let d_forward = forward * forward_dir;
         let d_right = strafe * right_dir;
-        let d_total = up_down + d_forward + d_right;
+        let velocity = up_down + d_forward + d_right;
 
-        self.camera.r#move(d_total);
-        self.pd.r#move(d_total);
-        self.camera.rotate(dyaw, dpitch);
+        if self.physics_on{
+            self.pd.sneaking = movement[2] < 0;
+            let walk = if self.pd.sneaking{ SNEAK_SPEED }else{ 1.0 };
+            self.pd.force.x = velocity.x * walk;
+            self.pd.force.z = velocity.z * walk;
+            if self.pd.climbing{
+                // letting go of both holds on where they are
+                self.pd.force.y = movement[2] as f32 * self.climb_speed;
+            }else if movement[2] > 0 && self.pd.on_ground{
+                self.pd.force.y = self.jump_speed;
+                self.pd.on_ground = false;
+            }
+        }else{
+            self.camera.r#move(velocity * dt);
+            self.pd.r#move(velocity * dt);
+        }
+    }
+
+    // call before a tick moves the player
+    pub fn start_tick(&mut self){
+        self.last_position = self.pd.position;
+    }
+
+    // puts the camera alpha of the way from where the player
+    // was before the last tick to where they are now. flying
+    // isn't ticked, so the camera is just put on the player
+    pub fn interpolate_camera(&mut self, alpha: f32){
+        let alpha = if self.physics_on{ alpha }else{ 1.0 };
+        let position = self.last_position + (self.pd.position - self.last_position) * alpha;
+        self.camera.set_pos(position);
+    }
+
+    // switches between walking and flying. either way the
+    // player starts out standing still
+    pub fn toggle_physics(&mut self){
+        self.physics_on = !self.physics_on;
+        self.pd.force = cgmath::Vector3::new(0.0, 0.0, 0.0);
+        self.pd.on_ground = false;
+        self.last_position = self.pd.position;
+    }
+
+    // breaks the block the player is looking at and picks it up.
+    // returns where the block was
+    pub fn break_block(&mut self, world: &mut World) -> Option<BlockPos>{
+        let hit = world.raycast(&Ray::from_camera(&self.camera), &RaycastSettings::default())?;
+        let block = world.get_block(hit.block)?;
+        if !world.set_block(hit.block, AIR_ID){ return None; }
+        // the state is lost, a log is a log whichever way it faced.
+        // anything that doesn't fit is gone
+        self.inventory.add(block_of(block), 1);
+        Some(hit.block)
+    }
+
+    // puts the selected item against the side of the block the
+    // player is looking at. returns where it went
+    pub fn place_block(&mut self, world: &mut World) -> Option<BlockPos>{
+        let item = self.inventory.selected_item()?;
+        let hit = world.raycast(&Ray::from_camera(&self.camera), &RaycastSettings::default())?;
+        let pos = hit.adjacent;
+        if world.get_block(pos) != Some(AIR_ID){ return None; }
+        let def = BLOCKS.get(item);
+        // can't put a solid block inside of the player, but they
+        // can stand in things like flowers
+        if def.solid && self.pd.overlaps_block(pos){ return None; }
+        let mut state = BlockState::new(item);
+        // logs point out from what they were put against, and
+        // slabs put under a block hang from it
+        if def.properties.facing{ state = state.with_facing(hit.face); }
+        if def.properties.half && hit.face == AbsolouteDirection::Down{ state = state.with_half(Half::Top); }
+        if !world.set_block(pos, state.id()){ return None; }
+        self.inventory.take_selected();
+        Some(pos)
+    }
+
+    pub fn to_save(&self) -> PlayerSave{
+        PlayerSave{
+            position: self.pd.position.into(),
+            yaw: self.camera.yaw.0,
+            pitch: self.camera.pitch.0,
+            inventory: self.inventory.clone()
+        }
+    }
+
+    // puts the player back how they were saved
+    pub fn load_save(&mut self, save: PlayerSave){
+        self.update_position(save.position.into());
+        self.last_position = self.pd.position;
+        self.camera.yaw = cgmath::Rad(save.yaw);
+        self.camera.pitch = cgmath::Rad(save.pitch);
+        self.inventory = save.inventory;
     }
 
     // getters
     pub fn get_camera_ref_mut(&mut self) -> &mut Camera{
         &mut self.camera
     }
+
+    pub fn get_inventory_mut(&mut self) -> &mut Inventory{
+        &mut self.inventory
+    }
 }
 
 impl PhysicsObject for Player{
@@ -67,4 +197,52 @@ impl PhysicsObject for Player{
         self.pd.position = new_position;
         self.camera.set_pos(new_position);
     }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::{FLOWER_ID, STONE_ID};
+    use crate::coords::ChunkId;
+    use crate::worldgen::WorldGenSettings;
+
+    // a block to stand on high above the ground, and a player
+    // looking straight down at it from height blocks up
+    fn looking_down(height: i32) -> (World, Player, BlockPos){
+        let mut world = World::new(0, WorldGenSettings::default(), None);
+        world.update(ChunkId{ x: 0, z: 0 });
+        let ground = BlockPos::new(8, 100, 8);
+        world.set_block(ground, *STONE_ID);
+        let mut player = Player::new(800, 600);
+        // the hitbox is centred on the position
+        let feet = ground.offset(0, height, 0).to_world_pos();
+        player.update_position(cgmath::Point3::new(feet.x, feet.y, feet.z) + cgmath::Vector3::new(0.5, 1.0, 0.5) * VOXEL_WIDTH);
+        player.camera.pitch = cgmath::Rad(-std::f32::consts::FRAC_PI_2);
+        (world, player, ground)
+    }
+
+    #[test]
+    fn blocks_go_against_the_face_looked_at(){
+        let (mut world, mut player, ground) = looking_down(3);
+        player.inventory.add(*STONE_ID, 2);
+        let above = ground.offset(0, 1, 0);
+        assert_eq!(player.place_block(&mut world), Some(above));
+        assert_eq!(world.get_block(above), Some(*STONE_ID));
+        assert_eq!(player.inventory.get(0).unwrap().count, 1);
+    }
+
+    #[test]
+    fn solid_blocks_cant_go_inside_the_player(){
+        let (mut world, mut player, ground) = looking_down(1);
+        let above = ground.offset(0, 1, 0);
+        player.inventory.add(*STONE_ID, 1);
+        assert_eq!(player.place_block(&mut world), None);
+        assert_eq!(world.get_block(above), Some(AIR_ID));
+        assert_eq!(player.inventory.selected_item(), Some(*STONE_ID));
+        // a flower can be stood in
+        player.inventory.take_selected();
+        player.inventory.add(*FLOWER_ID, 1);
+        assert_eq!(player.place_block(&mut world), Some(above));
+        assert_eq!(world.get_block(above), Some(*FLOWER_ID));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,178 @@
+use cgmath::InnerSpace;
+
+use crate::blocks::{RenderShape, AIR_ID, BLOCKS};
+use crate::coords::{BlockPos, WorldPos};
+use crate::direction::AbsolouteDirection;
+use crate::render::camera::Camera;
+use crate::render::voxel::VOXEL_WIDTH;
+
+// how far the player can reach, in metres
+pub const DEFAULT_REACH: f32 = 5.0;
+
+pub struct Ray{
+    pub origin: WorldPos,
+    // doesn't need to be normalized
+    pub direction: cgmath::Vector3<f32>
+}
+
+impl Ray{
+    pub fn new(origin: WorldPos, direction: cgmath::Vector3<f32>) -> Self{
+        Self{ origin, direction }
+    }
+
+    // the ray the player is looking along
+    pub fn from_camera(camera: &Camera) -> Self{
+        Self::new(camera.get_position().into(), camera.get_forward())
+    }
+}
+
+pub struct RaycastSettings{
+    // in metres
+    pub reach: f32,
+    // whether the ray goes through transparent blocks like
+    // water and glass instead of stopping on them
+    pub skip_transparent: bool
+}
+
+impl Default for RaycastSettings{
+    fn default() -> Self{
+        Self{ reach: DEFAULT_REACH, skip_transparent: false }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit{
+    pub block: BlockPos,
+    // the side of the block the ray went in through
+    pub face: AbsolouteDirection,
+    // from the start of the ray to where it hit, in metres
+    pub distance: f32,
+    // the cell in front of the face that was hit, where a
+    // placed block would go
+    pub adjacent: BlockPos
+}
+
+// walks the ray through every cell it passes, in order, until
+// it finds a block to stop on. blocks are looked up with
+// get_block so this doesn't need a world to work. cells it
+// returns None for are passed through. the cell the ray starts
+// in is never hit
+pub fn raycast<F: Fn(BlockPos) -> Option<usize>>(ray: &Ray, settings: &RaycastSettings, get_block: F) -> Option<RayHit>{
+    if ray.direction.magnitude2() == 0.0{ return None; }
+    let direction = ray.direction.normalize();
+    // distances along the ray are in metres
+    let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+    let dir = [direction.x, direction.y, direction.z];
+
+    let mut cell = ray.origin.to_block_pos();
+    let mut step = [0; 3];
+    // how far along the ray the next border on each axis is
+    let mut t_max = [f32::INFINITY; 3];
+    // how far along the ray it takes to cross a whole block on each axis
+    let mut t_delta = [f32::INFINITY; 3];
+    // the sides of the starting cell
+    let (low, high) = (cell.to_world_pos(), cell.offset(1, 1, 1).to_world_pos());
+    let (low, high) = ([low.x, low.y, low.z], [high.x, high.y, high.z]);
+    for axis in 0..3{
+        if dir[axis] > 0.0{
+            step[axis] = 1;
+            t_max[axis] = (high[axis] - origin[axis]) / dir[axis];
+        }else if dir[axis] < 0.0{
+            step[axis] = -1;
+            t_max[axis] = (origin[axis] - low[axis]) / -dir[axis];
+        }
+        if step[axis] != 0{ t_delta[axis] = VOXEL_WIDTH / dir[axis].abs(); }
+    }
+
+    loop{
+        let axis = if t_max[0] <= t_max[1] && t_max[0] <= t_max[2]{ 0 }
+            else if t_max[1] <= t_max[2]{ 1 }
+            else{ 2 };
+        let t = t_max[axis];
+        if t > settings.reach{ return None; }
+        t_max[axis] += t_delta[axis];
+        // the ray comes in through the side facing back at it
+        let face = match (axis, step[axis]){
+            (0, 1) => AbsolouteDirection::West,
+            (0, _) => AbsolouteDirection::East,
+            (1, 1) => AbsolouteDirection::Down,
+            (1, _) => AbsolouteDirection::Up,
+            (_, 1) => AbsolouteDirection::South,
+            _ => AbsolouteDirection::North
+        };
+        cell = match axis{
+            0 => cell.offset(step[0], 0, 0),
+            1 => cell.offset(0, step[1], 0),
+            _ => cell.offset(0, 0, step[2])
+        };
+
+        let block = match get_block(cell){
+            Some(b) if b != AIR_ID => b,
+            _ => continue
+        };
+        let def = BLOCKS.of_state(block);
+        if def.shape == RenderShape::None || (settings.skip_transparent && def.transparency){ continue; }
+        return Some(RayHit{
+            block: cell,
+            face,
+            distance: t,
+            adjacent: cell.neighbor(face)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::{STONE_ID, WATER_ID};
+
+    // stone everywhere below y = 0, with water at y = 0
+    fn ground(pos: BlockPos) -> Option<usize>{
+        match pos.y{
+            y if y < 0 => Some(*STONE_ID),
+            0 => Some(*WATER_ID),
+            _ => Some(AIR_ID)
+        }
+    }
+
+    #[test]
+    fn looking_down_hits_the_top(){
+        let ray = Ray::new(WorldPos::new(0.1, 1.1, 0.1), cgmath::Vector3::new(0.0, -1.0, 0.0));
+        let hit = raycast(&ray, &RaycastSettings::default(), ground).unwrap();
+        assert_eq!(hit.block, BlockPos::new(0, 0, 0));
+        assert_eq!(hit.face, AbsolouteDirection::Up);
+        assert_eq!(hit.adjacent, BlockPos::new(0, 1, 0));
+        assert!((hit.distance - 0.85).abs() < 0.0001);
+        // going through the water lands on the stone under it
+        let settings = RaycastSettings{ skip_transparent: true, ..Default::default() };
+        let hit = raycast(&ray, &settings, ground).unwrap();
+        assert_eq!(hit.block, BlockPos::new(0, -1, 0));
+        assert!((hit.distance - 1.1).abs() < 0.0001);
+    }
+
+    #[test]
+    fn faces_match_the_side_entered(){
+        // a single block at (-3, 0, 0), looked at from the east
+        let wall = |pos: BlockPos| Some(if pos == BlockPos::new(-3, 0, 0){ *STONE_ID }else{ AIR_ID });
+        let ray = Ray::new(WorldPos::new(0.1, 0.1, 0.1), cgmath::Vector3::new(-1.0, 0.0, 0.0));
+        let hit = raycast(&ray, &RaycastSettings::default(), wall).unwrap();
+        assert_eq!(hit.block, BlockPos::new(-3, 0, 0));
+        assert_eq!(hit.face, AbsolouteDirection::East);
+        assert_eq!(hit.adjacent, BlockPos::new(-2, 0, 0));
+        // a steep diagonal still finds it, through the top
+        let ray = Ray::new(WorldPos::new(-0.6, 0.6, 0.1), cgmath::Vector3::new(-0.1, -1.0, 0.0));
+        let hit = raycast(&ray, &RaycastSettings::default(), wall).unwrap();
+        assert_eq!(hit.face, AbsolouteDirection::Up);
+    }
+
+    #[test]
+    fn reach_is_a_limit(){
+        let ray = Ray::new(WorldPos::new(0.1, 3.1, 0.1), cgmath::Vector3::new(0.0, -1.0, 0.0));
+        let short = RaycastSettings{ reach: 2.0, ..Default::default() };
+        assert!(raycast(&ray, &short, ground).is_none());
+        assert!(raycast(&ray, &RaycastSettings::default(), ground).is_some());
+        // nothing to hit looking up
+        let ray = Ray::new(WorldPos::new(0.1, 3.1, 0.1), cgmath::Vector3::new(0.0, 1.0, 0.0));
+        assert!(raycast(&ray, &RaycastSettings::default(), ground).is_none());
+    }
+}
\ No newline at end of file
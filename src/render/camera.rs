@@ -58,20 +58,28 @@ impl Camera{
     }
 
     pub fn calc_view(&self) -> cgmath::Matrix4<f32> {
-        let (sin_pitch, cos_pitch) = self.pitch.0.sin_cos();
-        let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
-
         cgmath::Matrix4::look_to_rh(
             self.position,
-            cgmath::Vector3::new(
-                cos_pitch * cos_yaw,
-                sin_pitch,
-                cos_pitch * sin_yaw
-            ).normalize(),
+            self.get_forward(),
             cgmath::Vector3::unit_y(),
         )
     }
 
+    // the direction the camera is looking in, from yaw and pitch
+    pub fn get_forward(&self) -> cgmath::Vector3<f32> {
+        let (sin_pitch, cos_pitch) = self.pitch.0.sin_cos();
+        let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
+        cgmath::Vector3::new(
+            cos_pitch * cos_yaw,
+            sin_pitch,
+            cos_pitch * sin_yaw
+        ).normalize()
+    }
+
+    pub fn get_position(&self) -> cgmath::Point3<f32> {
+        self.position
+    }
+
     pub fn calc_matrix(&self) -> cgmath::Matrix4<f32> {
         let view = self.calc_view();
         let proj = self.projection.calc_matrix();
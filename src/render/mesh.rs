@@ -15,6 +15,8 @@ pub trait MeshTrait<V: VertexTrait>{
     fn get_vertex_desc<'a>() -> VertexBufferLayout<'a>;
     // returns name of the shader the mesh is made for
     fn get_shader() -> String;
+    // the shader used for the see through parts of the mesh
+    fn get_translucent_shader() -> String { Self::get_shader() }
     fn get_indices(&self) -> &wgpu::Buffer;
     fn get_vertices(&self) -> &wgpu::Buffer;
     fn get_num_indices(&self) -> u32;
@@ -1,3 +1,4 @@
+use crate::coords::BlockPos;
 use crate::direction::AbsolouteDirection;
 use super::vertex::VertexTrait;
 use super::mesh::MeshTrait;
@@ -36,134 +37,63 @@ impl<V: VertexTrait> Quad<V>{
 }
 
 
-pub fn south_face(pos: [i32; 3], color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
-    let pos = [
-        pos[0] as f32 * VOXEL_WIDTH,
-        pos[1] as f32 * VOXEL_WIDTH,
-        pos[2] as f32 * VOXEL_WIDTH
-    ];
-    [
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32, pos[2] as f32], direction: AbsolouteDirection::South.into()},
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32, pos[2] as f32], direction: AbsolouteDirection::South.into()},
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32], direction: AbsolouteDirection::South.into()},
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32], direction: AbsolouteDirection::South.into()}
-    ]
+// one side of a box inside of the block at pos. min and max are
+// in blocks, so (0, 0, 0) to (1, 1, 1) is the whole block
+pub fn box_face(direction: AbsolouteDirection, pos: BlockPos, min: [f32; 3], max: [f32; 3], color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
+    let corner = pos.to_world_pos();
+    let (x0, y0, z0) = (corner.x + min[0]*VOXEL_WIDTH, corner.y + min[1]*VOXEL_WIDTH, corner.z + min[2]*VOXEL_WIDTH);
+    let (x1, y1, z1) = (corner.x + max[0]*VOXEL_WIDTH, corner.y + max[1]*VOXEL_WIDTH, corner.z + max[2]*VOXEL_WIDTH);
+    // counter clockwise when looking at the face from outside
+    let positions = match direction{
+        AbsolouteDirection::South => [[x0, y0, z0], [x1, y0, z0], [x0, y1, z0], [x1, y1, z0]],
+        AbsolouteDirection::North => [[x1, y0, z1], [x0, y0, z1], [x1, y1, z1], [x0, y1, z1]],
+        AbsolouteDirection::East => [[x1, y0, z0], [x1, y0, z1], [x1, y1, z0], [x1, y1, z1]],
+        AbsolouteDirection::West => [[x0, y0, z1], [x0, y0, z0], [x0, y1, z1], [x0, y1, z0]],
+        AbsolouteDirection::Up => [[x0, y1, z0], [x1, y1, z0], [x0, y1, z1], [x1, y1, z1]],
+        AbsolouteDirection::Down => [[x0, y0, z1], [x1, y0, z1], [x0, y0, z0], [x1, y0, z0]]
+    };
+    positions.map(|position| VoxelVertex{ color, block_id, position, direction: direction.into(), alpha: 1.0 })
 }
 
-pub fn north_face(pos: [i32; 3], color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
-    let pos = [
-        pos[0] as f32 * VOXEL_WIDTH,
-        pos[1] as f32 * VOXEL_WIDTH,
-        pos[2] as f32 * VOXEL_WIDTH
-    ];
+// two quads crossing diagonally through the block, each drawn
+// from both sides since back faces are culled
+pub fn cross_faces(pos: BlockPos, color: [f32; 3], block_id: u32) -> [[VoxelVertex; 4]; 4]{
+    let corner = pos.to_world_pos();
+    let (x0, y0, z0) = (corner.x, corner.y, corner.z);
+    let (x1, y1, z1) = (x0 + VOXEL_WIDTH, y0 + VOXEL_WIDTH, z0 + VOXEL_WIDTH);
+    // plants are lit like the top of a block so they don't look dim
+    let vertex = |position: [f32; 3]| VoxelVertex{ color, block_id, position, direction: AbsolouteDirection::Up.into(), alpha: 1.0 };
     [
-        VoxelVertex{ color,
-            block_id, 
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::North.into()},
-        VoxelVertex{ color,
-            block_id,
-             position: [pos[0] as f32, pos[1] as f32, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::North.into()},
-        VoxelVertex{ color,
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::North.into()},
-        VoxelVertex{ color,
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::North.into()}
+        [[x0, y0, z0], [x1, y0, z1], [x0, y1, z0], [x1, y1, z1]].map(vertex),
+        [[x1, y0, z1], [x0, y0, z0], [x1, y1, z1], [x0, y1, z0]].map(vertex),
+        [[x0, y0, z1], [x1, y0, z0], [x0, y1, z1], [x1, y1, z0]].map(vertex),
+        [[x1, y0, z0], [x0, y0, z1], [x1, y1, z0], [x0, y1, z1]].map(vertex)
     ]
 }
 
-pub fn east_face(pos: [i32; 3], color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
-    let pos = [
-        pos[0] as f32 * VOXEL_WIDTH,
-        pos[1] as f32 * VOXEL_WIDTH,
-        pos[2] as f32 * VOXEL_WIDTH
-    ];
-    [
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32, pos[2] as f32], direction: AbsolouteDirection::East.into()},
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::East.into()},
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32], direction: AbsolouteDirection::East.into()},
-        VoxelVertex{ color,
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::East.into()}
-    ]
+const FULL_MIN: [f32; 3] = [0.0, 0.0, 0.0];
+const FULL_MAX: [f32; 3] = [1.0, 1.0, 1.0];
+
+pub fn south_face(pos: BlockPos, color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
+    box_face(AbsolouteDirection::South, pos, FULL_MIN, FULL_MAX, color, block_id)
 }
 
-pub fn west_face(pos: [i32; 3], color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
-    let pos = [
-        pos[0] as f32 * VOXEL_WIDTH,
-        pos[1] as f32 * VOXEL_WIDTH,
-        pos[2] as f32 * VOXEL_WIDTH
-    ];
-    [
-        VoxelVertex{ color,  
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::West.into()},
-        VoxelVertex{ color,  
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32, pos[2] as f32], direction: AbsolouteDirection::West.into()},
-        VoxelVertex{ color,  
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::West.into()},
-        VoxelVertex{ color,  
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32], direction: AbsolouteDirection::West.into()}
-    ]
+pub fn north_face(pos: BlockPos, color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
+    box_face(AbsolouteDirection::North, pos, FULL_MIN, FULL_MAX, color, block_id)
 }
 
-pub fn top_face(pos: [i32; 3], color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
-    let pos = [
-        pos[0] as f32 * VOXEL_WIDTH,
-        pos[1] as f32 * VOXEL_WIDTH,
-        pos[2] as f32 * VOXEL_WIDTH
-    ];
-    [
-            VoxelVertex{ color, 
-                block_id,
-                position: [pos[0] as f32, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32], direction: AbsolouteDirection::Up.into()},
-            VoxelVertex{ color, 
-                block_id,
-                position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32], direction: AbsolouteDirection::Up.into()},
-            VoxelVertex{ color, 
-                block_id,
-                position: [pos[0] as f32, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::Up.into()},
-            VoxelVertex{ color, 
-                block_id,
-                position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32 + VOXEL_WIDTH, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::Up.into()}
-    ]
+pub fn east_face(pos: BlockPos, color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
+    box_face(AbsolouteDirection::East, pos, FULL_MIN, FULL_MAX, color, block_id)
 }
 
-pub fn bottom_face(pos: [i32; 3], color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
-    let pos = [
-        pos[0] as f32 * VOXEL_WIDTH,
-        pos[1] as f32 * VOXEL_WIDTH,
-        pos[2] as f32 * VOXEL_WIDTH
-    ];
-    [
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::Down.into()},
-        VoxelVertex{ color,
-            block_id,
-             position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32, pos[2] as f32 + VOXEL_WIDTH], direction: AbsolouteDirection::Down.into()},
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32, pos[1] as f32, pos[2] as f32], direction: AbsolouteDirection::Down.into()},
-        VoxelVertex{ color, 
-            block_id,
-            position: [pos[0] as f32 + VOXEL_WIDTH, pos[1] as f32, pos[2] as f32], direction: AbsolouteDirection::Down.into()}
-    ]
+pub fn west_face(pos: BlockPos, color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
+    box_face(AbsolouteDirection::West, pos, FULL_MIN, FULL_MAX, color, block_id)
+}
+
+pub fn top_face(pos: BlockPos, color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
+    box_face(AbsolouteDirection::Up, pos, FULL_MIN, FULL_MAX, color, block_id)
+}
+
+pub fn bottom_face(pos: BlockPos, color: [f32; 3], block_id: u32) -> [VoxelVertex; 4]{
+    box_face(AbsolouteDirection::Down, pos, FULL_MIN, FULL_MAX, color, block_id)
 }
\ No newline at end of file
@@ -13,7 +13,10 @@ pub struct Shader{
 }
 
 impl Shader{
-    pub fn new<V: VertexTrait>(file_data: &str, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, bind_groups: Vec<wgpu::BindGroup>, bind_group_layouts: &[&wgpu::BindGroupLayout]) -> Self{
+    // a translucent shader blends with what is already drawn and
+    // leaves the depth buffer alone, so it has to be drawn after
+    // everything solid and from back to front
+    pub fn new<V: VertexTrait>(file_data: &str, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, bind_groups: Vec<wgpu::BindGroup>, bind_group_layouts: &[&wgpu::BindGroupLayout], translucent: bool) -> Self{
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
             source: wgpu::ShaderSource::Wgsl(file_data.into()),
@@ -40,7 +43,7 @@ impl Shader{
                     entry_point: "fs_main",
                     targets: &[Some(wgpu::ColorTargetState {
                         format: config.format,
-                        blend: Some(wgpu::BlendState::REPLACE),
+                        blend: Some(if translucent{ wgpu::BlendState::ALPHA_BLENDING }else{ wgpu::BlendState::REPLACE }),
                         write_mask: wgpu::ColorWrites::ALL,
                     })],
                 }),
@@ -48,7 +51,8 @@ impl Shader{
                     topology: wgpu::PrimitiveTopology::TriangleList,
                     strip_index_format: None,
                     front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
+                    // see through faces can be looked at from behind, ex: from underwater
+                    cull_mode: if translucent{ None }else{ Some(wgpu::Face::Back) },
                     // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
                     polygon_mode: wgpu::PolygonMode::Fill,
                     // Requires Features::DEPTH_CLIP_CONTROL
@@ -58,7 +62,7 @@ impl Shader{
                 },
                 depth_stencil:  Some(wgpu::DepthStencilState {
                     format: texture::DEPTH_FORMAT,
-                    depth_write_enabled: true,
+                    depth_write_enabled: !translucent,
                     depth_compare: wgpu::CompareFunction::Less,
                     stencil: wgpu::StencilState::default(),
                     bias: wgpu::DepthBiasState::default(),
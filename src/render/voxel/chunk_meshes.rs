@@ -0,0 +1,95 @@
+use hashbrown::{HashMap, HashSet};
+
+use crate::blocks::{SectionId, CHUNK_WIDTH, SECTIONS_PER_CHUNK, SECTION_HEIGHT};
+use crate::coords::{ChunkId, WorldPos};
+use crate::world::World;
+
+use super::{SectionMeshes, VoxelMesh, VOXEL_WIDTH};
+
+// rebuilding a mesh isn't free, so only this many dirty
+// sections are rebuilt each frame. the closest go first
+const SECTION_REBUILDS_PER_FRAME: usize = 32;
+
+// the gpu side of the world. holds a mesh for every section of
+// every loaded chunk and follows the world as chunks stream in
+// and out. sections with nothing to draw have no mesh
+pub struct ChunkMeshes{
+    meshes: HashMap<ChunkId, Vec<SectionMeshes>>,
+    // sections waiting to be rebuilt
+    dirty: HashSet<SectionId>
+}
+
+impl ChunkMeshes{
+    pub fn new() -> Self{
+        Self{ meshes: HashMap::new(), dirty: HashSet::new() }
+    }
+
+    pub fn update(&mut self, world: &mut World, device: &wgpu::Device){
+        let changes = world.take_chunk_changes();
+        // dropping the meshes frees their buffers
+        for id in &changes.unloaded{
+            self.meshes.remove(id);
+        }
+        if !changes.unloaded.is_empty(){
+            self.dirty.retain(|s| !changes.unloaded.contains(&s.chunk));
+        }
+        // new chunks are meshed right away. the world already
+        // limits how many load each frame
+        for id in &changes.loaded{
+            for index in 0..SECTIONS_PER_CHUNK{
+                self.rebuild_section(world, SectionId::new(*id, index), device);
+            }
+        }
+        self.dirty.extend(changes.dirty);
+
+        if self.dirty.is_empty(){ return; }
+        let center = world.get_center().unwrap_or(ChunkId{ x: 0, z: 0 });
+        let mut to_build: Vec<SectionId> = self.dirty.iter().copied().collect();
+        to_build.sort_by_key(|s| (s.chunk.x - center.x).pow(2) + (s.chunk.z - center.z).pow(2));
+        for section in to_build.into_iter().take(SECTION_REBUILDS_PER_FRAME){
+            self.dirty.remove(&section);
+            self.rebuild_section(world, section, device);
+        }
+    }
+
+    fn rebuild_section(&mut self, world: &World, section: SectionId, device: &wgpu::Device){
+        let neighbors = match world.get_neighbors(&section.chunk){
+            Some(n) => n,
+            None => return
+        };
+        let sections = self.meshes.entry(section.chunk).or_insert_with(|| {
+            let mut v = Vec::with_capacity(SECTIONS_PER_CHUNK);
+            v.resize_with(SECTIONS_PER_CHUNK, SectionMeshes::default);
+            v
+        });
+        sections[section.index] = VoxelMesh::from_section(&neighbors, section.index, device);
+    }
+
+    pub fn get_meshes(&self) -> Vec<&VoxelMesh>{
+        self.meshes.values()
+            .flat_map(|sections| sections.iter().filter_map(|s| s.opaque.as_ref()))
+            .collect()
+    }
+
+    // blending only looks right when the furthest faces are drawn
+    // first, so these come sorted by how far their section is from
+    // the camera
+    pub fn get_translucent_meshes(&self, camera: WorldPos) -> Vec<&VoxelMesh>{
+        let mut meshes: Vec<(f32, &VoxelMesh)> = self.meshes.iter()
+            .flat_map(|(id, sections)| sections.iter().enumerate().filter_map(move |(index, s)| {
+                let mesh = s.translucent.as_ref()?;
+                Some((section_distance(SectionId::new(*id, index), camera), mesh))
+            }))
+            .collect();
+        meshes.sort_by(|a, b| b.0.total_cmp(&a.0));
+        meshes.into_iter().map(|(_, mesh)| mesh).collect()
+    }
+}
+
+// squared distance from a point to the middle of a section
+fn section_distance(section: SectionId, point: WorldPos) -> f32{
+    let corner = section.chunk.to_block_pos().offset(0, (section.index * SECTION_HEIGHT) as i32, 0).to_world_pos();
+    let half = CHUNK_WIDTH as f32 * VOXEL_WIDTH / 2.0;
+    let (dx, dy, dz) = (corner.x + half - point.x, corner.y + half - point.y, corner.z + half - point.z);
+    dx*dx + dy*dy + dz*dz
+}
\ No newline at end of file
@@ -1,8 +1,10 @@
 mod voxel;
 mod voxel_mesh;
+mod chunk_meshes;
 
 pub use voxel::*;
-pub use voxel_mesh::VoxelMesh;
+pub use voxel_mesh::{SectionMeshes, VoxelMesh};
+pub use chunk_meshes::ChunkMeshes;
 
 pub const VOXEL_WIDTH: f32 = 0.25;
 // this is stored since it is commonly used
@@ -1,5 +1,6 @@
 use bytemuck::{Zeroable, Pod};
 
+use crate::coords::BlockPos;
 use crate::render::vertex::VertexTrait;
 use crate::render::mesh::MeshTrait;
 use crate::render::quad::{
@@ -16,7 +17,8 @@ pub struct VoxelVertex{
     pub position: [f32; 3],
     pub color: [f32; 3],
     pub block_id: u32,
-    pub direction: f32 // can be any AbsolouteDirection
+    pub direction: f32, // can be any AbsolouteDirection
+    pub alpha: f32
 }
 
 impl VertexTrait for VoxelVertex{
@@ -50,6 +52,12 @@ impl VertexTrait for VoxelVertex{
                     offset: (std::mem::size_of::<[f32; 3]>() * 2 + std::mem::size_of::<u32>()) as wgpu::BufferAddress,
                     shader_location: 3,
                     format: wgpu::VertexFormat::Float32
+                },
+                // alpha
+                wgpu::VertexAttribute {
+                    offset: (std::mem::size_of::<[f32; 3]>() * 2 + std::mem::size_of::<u32>() + std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32
                 }
             ]
         }
@@ -58,7 +66,7 @@ impl VertexTrait for VoxelVertex{
 
 impl VoxelVertex{
     pub fn new(position: [f32; 3], direction: f32, color: [f32; 3], block_id: u32) -> Self{
-        Self { position, direction, block_id, color }
+        Self { position, direction, block_id, color, alpha: 1.0 }
     }
 }
 
@@ -96,12 +104,13 @@ impl Voxel{
         let bbr = VoxelVertex{ position: [pos[0] as f32 + right, pos[1] as f32 + down, pos[2] as f32 + back], direction: 3};
         */
 
-        let south = south_face(pos, color, 1);
-        let north = north_face(pos, color, 1);
-        let west = west_face(pos, color, 1);
-        let east = east_face(pos, color, 1);
-        let bottom = bottom_face(pos, color, 1);
-        let top = top_face(pos, color, 1);
+        let block_pos = BlockPos::new(pos[0], pos[1], pos[2]);
+        let south = south_face(block_pos, color, 1);
+        let north = north_face(block_pos, color, 1);
+        let west = west_face(block_pos, color, 1);
+        let east = east_face(block_pos, color, 1);
+        let bottom = bottom_face(block_pos, color, 1);
+        let top = top_face(block_pos, color, 1);
 
         let mut vertices: Vec<VoxelVertex> = Vec::with_capacity(16);
         let mut indices: Vec<u32> = Vec::new();
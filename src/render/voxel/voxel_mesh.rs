@@ -1,13 +1,14 @@
 use wgpu::util::DeviceExt;
 
-use crate::blocks::{ChunkData, ChunkId, BLOCK_ARRAY, CHUNK_HEIGHT, CHUNK_WIDTH};
+use crate::coords::{BlockPos, LocalPos};
+use crate::blocks::{
+    block_boxes, block_of, face_color, side_is_covered, BlockBox, BlockState,
+    ChunkNeighbors, RenderShape, SectionState, BLOCKS, CHUNK_WIDTH, SECTION_HEIGHT
+};
+use crate::direction::AbsolouteDirection;
 use crate::render::mesh::MeshTrait;
 use crate::render::vertex::VertexTrait;
-use crate::render::quad::{
-    north_face, south_face, east_face,
-    west_face, bottom_face, top_face,
-    QUAD_INDICES
-};
+use crate::render::quad::{box_face, cross_faces, QUAD_INDICES};
 
 use super::{Voxel, VoxelVertex};
 
@@ -21,6 +22,7 @@ pub struct VoxelMesh{
 impl MeshTrait<VoxelVertex> for VoxelMesh{
     fn blank() -> Self { unimplemented!() }
     fn get_shader() -> String { "voxel".into() }
+    fn get_translucent_shader() -> String { "voxel_translucent".into() }
     fn get_vertex_desc<'a>() -> wgpu::VertexBufferLayout<'a> { VoxelVertex::get_desc() }
 
     // these should be switched to slices later
@@ -91,72 +93,119 @@ impl VoxelMesh{
         Self { verts, indices, vert_buf, index_buf }
     }
 
-    pub fn from_blocks(chunk_id: &ChunkId, blocks: &ChunkData, device: &wgpu::Device) -> Self{
-        let mut x_offset = 0usize;
-        let mut y_offset = 0usize;
-        let mut z_offset = 0usize;
-        let mut verts: Vec<VoxelVertex> = Vec::new();
-        let mut indices: Vec<u32> = Vec::new();
-        let mut faces = 0;
-        for square in blocks{
-            y_offset = 0;
-            for col in square{
-                z_offset = 0;
-                for block in col{
-                    // block gives the ID of the block stored in the current position
-                    if BLOCK_ARRAY[*block].transparency{ z_offset += 1; continue; }
-                    let pos = [
-                        chunk_id.x * CHUNK_WIDTH as i32 + x_offset as i32,
-                        y_offset as i32,
-                        chunk_id.z * CHUNK_WIDTH as i32 + z_offset as i32
-                    ];
-                    // start making faces
-                    let mut faces_on_this_block = 0;
-                    if x_offset == 0 || BLOCK_ARRAY[blocks[x_offset-1][y_offset][z_offset]].transparency{
-                        verts.extend(west_face(pos, BLOCK_ARRAY[*block].color, *block as u32));
-                        faces += 1;
-                        faces_on_this_block += 1;
-                    }
-                    if x_offset == CHUNK_WIDTH-1 || BLOCK_ARRAY[blocks[x_offset+1][y_offset][z_offset]].transparency{
-                        verts.extend(east_face(pos, BLOCK_ARRAY[*block].color, *block as u32));
-                        faces += 1;
-                        faces_on_this_block += 1;
-                    }
-                    if z_offset == 0 || BLOCK_ARRAY[blocks[x_offset][y_offset][z_offset-1]].transparency{
-                        verts.extend(south_face(pos, BLOCK_ARRAY[*block].color, *block as u32));
-                        faces += 1;
-                        faces_on_this_block += 1;
-                    }
-                    if z_offset == CHUNK_WIDTH-1 || BLOCK_ARRAY[blocks[x_offset][y_offset][z_offset+1]].transparency{
-                        verts.extend(north_face(pos, BLOCK_ARRAY[*block].color, *block as u32));
-                        faces += 1;
-                        faces_on_this_block += 1;
-                    }
-                    if y_offset == 0 || BLOCK_ARRAY[blocks[x_offset][y_offset-1][z_offset]].transparency{
-                        verts.extend(bottom_face(pos, BLOCK_ARRAY[*block].color, *block as u32));
-                        faces += 1;
-                        faces_on_this_block += 1;
+    // builds the meshes for one SECTION_HEIGHT tall slice of a chunk.
+    // see through blocks go in their own mesh so they can be drawn
+    // after everything solid
+    pub fn from_section(neighbors: &ChunkNeighbors, section_index: usize, device: &wgpu::Device) -> SectionMeshes{
+        let section = neighbors.center.get_section(section_index);
+        let state = section.get_state();
+        if state == SectionState::Empty || neighbors.section_is_hidden(section_index){
+            return SectionMeshes::default();
+        }
+
+        let chunk_id = neighbors.center.get_id();
+        let base_y = (section_index * SECTION_HEIGHT) as i32;
+        let mut opaque = MeshBuilder::default();
+        let mut translucent = MeshBuilder::default();
+        // blocks past the edge of the chunk are looked up in the
+        // neighboring chunks. if that chunk isn't loaded the face
+        // is drawn, and gets culled when the neighbor loads and
+        // this mesh is rebuilt
+        let covered = |block: usize, face: &BlockBox, side: AbsolouteDirection, x: i32, y: i32, z: i32| -> bool{
+            let neighbor = match neighbors.get_block(x, y, z){
+                Some(b) => b,
+                None => return false
+            };
+            let def = BLOCKS.of_state(neighbor);
+            // the inside of a body of water or a pane of glass isn't
+            // drawn, but anything can be seen through a different block
+            if def.transparency{
+                return block_of(neighbor) == block_of(block) && def.shape == RenderShape::Cube;
+            }
+            // most blocks are cubes, which cover everything
+            if def.is_opaque_cube(){ return true; }
+            side_is_covered(face, side, &block_boxes(def, BlockState::from_id(neighbor)))
+        };
+        let last = CHUNK_WIDTH as i32 - 1;
+        let top = SECTION_HEIGHT as i32 - 1;
+        for x in 0..CHUNK_WIDTH as i32{
+            for sy in 0..SECTION_HEIGHT as i32{
+                for z in 0..CHUNK_WIDTH as i32{
+                    // inside an opaque section only the outer shell
+                    // of blocks can have a neighbor to show through
+                    if state == SectionState::Opaque
+                        && x > 0 && x < last && z > 0 && z < last && sy > 0 && sy < top{
+                        continue;
                     }
-                    if y_offset == CHUNK_HEIGHT-1 || BLOCK_ARRAY[blocks[x_offset][y_offset+1][z_offset]].transparency{
-                        verts.extend(top_face(pos, BLOCK_ARRAY[*block].color, *block as u32));
-                        faces += 1;
-                        faces_on_this_block += 1;
+                    let block = section.get(x as usize, sy as usize, z as usize);
+                    let def = BLOCKS.of_state(block);
+                    if def.shape == RenderShape::None{ continue; }
+                    let y = base_y + sy;
+                    let pos = BlockPos::from_local(chunk_id, LocalPos{ x: x as usize, y: y as usize, z: z as usize });
+                    // the state decides the shape and colors of the block
+                    let block_state = BlockState::from_id(block);
+                    let block_id = block_of(block) as u32;
+                    let builder = if def.is_translucent(){ &mut translucent }else{ &mut opaque };
+                    if def.shape == RenderShape::Cross{
+                        let color = face_color(def, block_state, AbsolouteDirection::Up);
+                        for face in cross_faces(pos, color, block_id){
+                            builder.add_face(face, def.alpha);
+                        }
+                        continue;
                     }
-
-                    for face in 0..faces_on_this_block{
-                        let new_indices = QUAD_INDICES.iter()
-                            .map(|i| i + ((faces-face) * 4) as u32)
-                            .collect::<Vec<u32>>();
-                        indices.extend(new_indices);
+                    let sides = [
+                        (AbsolouteDirection::West, x-1, y, z),
+                        (AbsolouteDirection::East, x+1, y, z),
+                        (AbsolouteDirection::South, x, y, z-1),
+                        (AbsolouteDirection::North, x, y, z+1),
+                        (AbsolouteDirection::Down, x, y-1, z),
+                        (AbsolouteDirection::Up, x, y+1, z)
+                    ];
+                    for bounds in block_boxes(def, block_state){
+                        for (side, nx, ny, nz) in sides{
+                            // sides that don't reach the edge of the
+                            // cell can't be covered by the next block
+                            if bounds.touches_side(side) && covered(block, &bounds, side, nx, ny, nz){ continue; }
+                            let color = face_color(def, block_state, side);
+                            builder.add_face(box_face(side, pos, bounds.min, bounds.max, color, block_id), def.alpha);
+                        }
                     }
-
-                    z_offset += 1;
                 }
-                y_offset += 1;
             }
-            x_offset += 1;
         }
 
+        SectionMeshes{
+            opaque: opaque.build(device),
+            translucent: translucent.build(device)
+        }
+    }
+}
+
+// the two meshes a section is drawn with. either one is None
+// when the section has nothing to draw in it
+#[derive(Default)]
+pub struct SectionMeshes{
+    pub opaque: Option<VoxelMesh>,
+    pub translucent: Option<VoxelMesh>
+}
+
+#[derive(Default)]
+struct MeshBuilder{
+    verts: Vec<VoxelVertex>,
+    indices: Vec<u32>
+}
+
+impl MeshBuilder{
+    fn add_face(&mut self, face: [VoxelVertex; 4], alpha: f32){
+        let offset = self.verts.len() as u32;
+        self.indices.extend(QUAD_INDICES.iter().map(|i| i + offset));
+        self.verts.extend(face.map(|v| VoxelVertex{ alpha, ..v }));
+    }
+
+    fn build(self, device: &wgpu::Device) -> Option<VoxelMesh>{
+        if self.indices.is_empty(){ return None; }
+        let Self{ verts, indices } = self;
+
         let vert_buf = device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Buffer"),
@@ -173,6 +222,6 @@ impl VoxelMesh{
             }
         );
 
-        Self { verts, indices, vert_buf, index_buf }
+        Some(VoxelMesh { verts, indices, vert_buf, index_buf })
     }
 }
\ No newline at end of file
@@ -124,7 +124,10 @@ impl WindowState{
     //   Render Methods
     //
 
-    pub fn render<V: VertexTrait, M: MeshTrait<V>>(&mut self, meshes: Vec<&M>, debug: bool) -> Result<(), wgpu::SurfaceError> {
+    // debug_text is drawn over the world when it is given.
+    // translucent meshes are drawn after the opaque ones in the
+    // order given, which should be back to front
+    pub fn render<V: VertexTrait, M: MeshTrait<V>>(&mut self, meshes: Vec<&M>, translucent: Vec<&M>, debug_text: Option<&str>) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -133,15 +136,12 @@ impl WindowState{
         // merge meshes here
         // ...
         // make buffers here. They must outlive renderpass
-        let mut buffers: Vec<(&wgpu::Buffer, &wgpu::Buffer, u32)> = Vec::new();
-        for mesh in meshes{
-            let vertex_buffer = mesh.get_vertices();
-            let index_buffer = mesh.get_indices();
-            buffers.push((vertex_buffer, index_buffer, mesh.get_num_indices()));
-        }
+        let passes = [
+            (self.shaders.get(&M::get_shader()).unwrap(), mesh_buffers(meshes)),
+            (self.shaders.get(&M::get_translucent_shader()).unwrap(), mesh_buffers(translucent))
+        ];
         // need to change scope so it doesnt matter i
         // forget drop(render_pass)
-        let shader = self.shaders.get(&M::get_shader()).unwrap();
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -170,25 +170,28 @@ impl WindowState{
                 occlusion_query_set: None
             });
 
-            render_pass.set_pipeline(shader.get_pipeline());
-            let mut index = 0u32;
-            for bind_group in shader.get_bind_groups(){
-                render_pass.set_bind_group(index, bind_group, &[]);
-                index += 1;
+            for (shader, buffers) in &passes{
+                if buffers.is_empty(){ continue; }
+                render_pass.set_pipeline(shader.get_pipeline());
+                let mut index = 0u32;
+                for bind_group in shader.get_bind_groups(){
+                    render_pass.set_bind_group(index, bind_group, &[]);
+                    index += 1;
+                }
+                // remove this later since there will only be one vertex and index buffers
+                for buffer_group in buffers{
+                    let vertex_buffer_slice = buffer_group.0.slice(..);
+                    let index_buffer_slice = buffer_group.1.slice(..);
+
+                    render_pass.set_vertex_buffer(0, vertex_buffer_slice);
+                    render_pass.set_index_buffer(index_buffer_slice, wgpu::IndexFormat::Uint32); 
+                    render_pass.draw_indexed(0..buffer_group.2, 0, 0..1);
+                }
             }
-            // remove this later since there will only be one vertex and index buffers
-            for buffer_group in &buffers{
-                let vertex_buffer_slice = buffer_group.0.slice(..);
-                let index_buffer_slice = buffer_group.1.slice(..);
-
-                render_pass.set_vertex_buffer(0, vertex_buffer_slice);
-                render_pass.set_index_buffer(index_buffer_slice, wgpu::IndexFormat::Uint32); 
-                render_pass.draw_indexed(0..buffer_group.2, 0, 0..1);
-            } 
         }
 
-        if debug{
-            self.text_data.pre_render("Debug", &self.device, &self.queue)
+        if let Some(text) = debug_text{
+            self.text_data.pre_render(text, &self.device, &self.queue)
                 .expect("Error loading pre-render text data");
             let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
@@ -223,8 +226,8 @@ impl WindowState{
     /// the stuff i made
     ///
 
-    pub fn register_shader<V: VertexTrait>(&mut self, name: String, file_data: &str, bind_groups: Vec<wgpu::BindGroup>, bind_group_layouts: &[&wgpu::BindGroupLayout]){
-        let shader = Shader::new::<V>(file_data, &self.device, &self.config, bind_groups, bind_group_layouts);
+    pub fn register_shader<V: VertexTrait>(&mut self, name: String, file_data: &str, bind_groups: Vec<wgpu::BindGroup>, bind_group_layouts: &[&wgpu::BindGroupLayout], translucent: bool){
+        let shader = Shader::new::<V>(file_data, &self.device, &self.config, bind_groups, bind_group_layouts, translucent);
         self.shaders.insert(name, shader);
     }
 
@@ -232,4 +235,10 @@ impl WindowState{
         let shader = self.shaders.get_mut(&name).unwrap();
         shader.update_bind_group(index, new_bind_group);
     }
+}
+
+fn mesh_buffers<'a, V: VertexTrait, M: MeshTrait<V>>(meshes: Vec<&'a M>) -> Vec<(&'a wgpu::Buffer, &'a wgpu::Buffer, u32)>{
+    meshes.into_iter()
+        .map(|mesh| (mesh.get_vertices(), mesh.get_indices(), mesh.get_num_indices()))
+        .collect()
 }
\ No newline at end of file
@@ -0,0 +1,141 @@
+use std::io;
+
+use crate::blocks::{block_of, BlockRegistry, AIR_ID};
+
+use super::bytes::invalid_data;
+
+// blocks are written to disk with save ids instead of the ids
+// the registry hands out. a save id is given to a block name once
+// and never changes, so chunks written before the block file was
+// reordered still read back as the same blocks
+pub struct BlockIdMap{
+    // save id -> block name
+    names: Vec<String>,
+    // runtime id -> save id
+    to_save: Vec<u32>,
+    // save id -> runtime id
+    from_save: Vec<usize>
+}
+
+impl BlockIdMap{
+    // names are the save's existing ids, in order. blocks the save
+    // hasn't seen yet get new ids on the end
+    pub fn new(mut names: Vec<String>, registry: &BlockRegistry) -> Self{
+        for name in registry.names(){
+            if !names.iter().any(|n| n == name){
+                names.push(name.into());
+            }
+        }
+        let from_save: Vec<usize> = names.iter()
+            .map(|name| match registry.id_of(name){
+                Some(id) => id,
+                None => {
+                    eprintln!("Block {} no longer exists and will load as air", name);
+                    AIR_ID
+                }
+            })
+            .collect();
+        let mut to_save = vec![0u32; registry.len()];
+        for (save_id, name) in names.iter().enumerate(){
+            if let Some(id) = registry.id_of(name){
+                to_save[id] = save_id as u32;
+            }
+        }
+        Self{ names, to_save, from_save }
+    }
+
+    pub fn names(&self) -> &[String]{
+        &self.names
+    }
+
+    pub fn save_id(&self, id: usize) -> u32{
+        self.to_save[id]
+    }
+
+    pub fn runtime_id(&self, save_id: u32) -> io::Result<usize>{
+        self.from_save.get(save_id as usize)
+            .copied()
+            .ok_or_else(|| invalid_data("unknown block id in save"))
+    }
+
+    // block states keep their properties, only the block is swapped
+    pub fn save_state(&self, state: usize) -> u32{
+        (state - block_of(state)) as u32 | self.save_id(block_of(state))
+    }
+
+    // removed blocks turn into plain air, whatever state they had
+    pub fn runtime_state(&self, saved: u32) -> io::Result<usize>{
+        let saved = saved as usize;
+        let block = block_of(saved);
+        match self.runtime_id(block as u32)?{
+            AIR_ID => Ok(AIR_ID),
+            id => Ok(saved - block + id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn reordered_blocks_keep_their_save_ids(){
+        let old = BlockRegistry::parse("[stone]\n[dirt]\n").unwrap();
+        let map = BlockIdMap::new(Vec::new(), &old);
+        let stone = map.save_id(old.expect_id("stone"));
+
+        // dirt moved in front of stone and glass was added
+        let new = BlockRegistry::parse("[dirt]\n[glass]\n[stone]\n").unwrap();
+        let map = BlockIdMap::new(map.names().to_vec(), &new);
+        assert_eq!(map.runtime_id(stone).unwrap(), new.expect_id("stone"));
+        assert_eq!(map.save_id(new.expect_id("stone")), stone);
+        assert_eq!(map.runtime_id(map.save_id(new.expect_id("glass"))).unwrap(), new.expect_id("glass"));
+        assert!(map.runtime_id(99).is_err());
+    }
+
+    #[test]
+    fn states_keep_their_properties(){
+        use crate::blocks::BlockState;
+        use crate::direction::AbsolouteDirection;
+
+        let old = BlockRegistry::parse("[stone]\n[log]\n").unwrap();
+        let map = BlockIdMap::new(Vec::new(), &old);
+        let log = BlockState::new(old.expect_id("log")).with_facing(AbsolouteDirection::East);
+        let saved = map.save_state(log.id());
+
+        let new = BlockRegistry::parse("[log]\n[stone]\n").unwrap();
+        let map = BlockIdMap::new(map.names().to_vec(), &new);
+        let loaded = BlockState::from_id(map.runtime_state(saved).unwrap());
+        assert_eq!(loaded, BlockState{ block: new.expect_id("log"), ..log });
+    }
+
+    #[test]
+    fn removed_blocks_load_as_air(){
+        let new = BlockRegistry::parse("[dirt]\n").unwrap();
+        let map = BlockIdMap::new(vec!["air".into(), "marble".into()], &new);
+        assert_eq!(map.runtime_id(1).unwrap(), AIR_ID);
+    }
+
+    #[test]
+    fn removed_blocks_lose_their_state(){
+        use crate::blocks::BlockState;
+        use crate::direction::AbsolouteDirection;
+
+        let old = BlockRegistry::parse("[log]
+properties = facing
+[wheat]
+properties = age
+").unwrap();
+        let map = BlockIdMap::new(Vec::new(), &old);
+        let log = BlockState::new(old.expect_id("log")).with_facing(AbsolouteDirection::West);
+        let wheat = BlockState::new(old.expect_id("wheat")).with_age(3);
+        let saved = [map.save_state(log.id()), map.save_state(wheat.id())];
+
+        let new = BlockRegistry::parse("[dirt]
+").unwrap();
+        let map = BlockIdMap::new(map.names().to_vec(), &new);
+        for state in saved{
+            assert_eq!(map.runtime_state(state).unwrap(), AIR_ID);
+        }
+    }
+}
\ No newline at end of file
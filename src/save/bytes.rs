@@ -0,0 +1,73 @@
+use std::io;
+
+// little endian helpers for the save formats
+
+pub struct ByteWriter{
+    buf: Vec<u8>
+}
+
+impl ByteWriter{
+    pub fn new() -> Self{
+        Self{ buf: Vec::new() }
+    }
+
+    pub fn write_u8(&mut self, v: u8){ self.buf.push(v); }
+    pub fn write_u16(&mut self, v: u16){ self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn write_u32(&mut self, v: u32){ self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn write_u64(&mut self, v: u64){ self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn write_f32(&mut self, v: f32){ self.write_u32(v.to_bits()); }
+    pub fn write_bytes(&mut self, v: &[u8]){ self.buf.extend_from_slice(v); }
+
+    pub fn into_inner(self) -> Vec<u8>{
+        self.buf
+    }
+}
+
+pub struct ByteReader<'a>{
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> ByteReader<'a>{
+    pub fn new(data: &'a [u8]) -> Self{
+        Self{ data, pos: 0 }
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> io::Result<&'a [u8]>{
+        if self.pos + len > self.data.len(){
+            return Err(invalid_data("unexpected end of save data"));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> io::Result<u8>{
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> io::Result<u16>{
+        Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn read_u32(&mut self) -> io::Result<u32>{
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_u64(&mut self) -> io::Result<u64>{
+        Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn read_f32(&mut self) -> io::Result<f32>{
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    // whether everything has been read
+    pub fn is_done(&self) -> bool{
+        self.pos == self.data.len()
+    }
+}
+
+pub fn invalid_data(msg: &str) -> io::Error{
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
@@ -0,0 +1,370 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::blocks::{Chunk, BLOCKS};
+use crate::coords::{BlockPos, ChunkId, LocalPos, WorldPos};
+use crate::inventory::Inventory;
+use crate::worldgen::WorldGenSettings;
+
+pub mod bytes;
+mod block_map;
+mod region;
+
+pub use block_map::BlockIdMap;
+
+use bytes::{ByteReader, ByteWriter, invalid_data};
+use region::RegionId;
+
+/*
+* A save is a folder:
+* - world.meta: save format version, the world generation
+*   settings as preset text and the block name of each save id
+* - region/: region files, see region.rs
+* - pending.dat: decoration blocks waiting on chunks that
+*   haven't been generated yet, and which chunks have already
+*   handed out their decorations
+* - player.dat: where the player is, where they are looking
+*   and what they are carrying
+* Chunks are only written once they've been changed, anything
+* else can just be generated again.
+*/
+
+const META_MAGIC: &[u8; 4] = b"OWSV";
+const PENDING_MAGIC: &[u8; 4] = b"OWPD";
+const PLAYER_MAGIC: &[u8; 4] = b"OWPL";
+// 1: just the version
+// 2: added world generation settings
+// 3: added block names, before this ids were fixed
+// 4: settings always say whether there are biomes. older
+//    saves only have the settings that existed when they were
+//    made, anything newer is left off so their terrain doesn't
+//    change under them
+pub const SAVE_VERSION: u32 = 4;
+// what the ids in version 1 and 2 saves meant
+const LEGACY_BLOCK_NAMES: [&str; 13] = [
+    "air", "grass", "dirt", "stone", "sand", "snow", "water",
+    "coal_ore", "iron_ore", "gold_ore", "diamond_ore", "log", "leaves"
+];
+// bumped whenever Chunk::write changes. old versions get
+// migrated in decode_chunk
+// 1: just the sections
+// 2: added scheduled block updates
+const CHUNK_VERSION: u32 = 2;
+
+// a chunk's scheduled block updates, in ticks from now
+pub type ChunkUpdates = Vec<(BlockPos, u32)>;
+// decoration blocks from other chunks, by the chunk they go in
+pub type PendingBlocks = HashMap<ChunkId, HashMap<BlockPos, usize>>;
+
+// everything about the player that is kept between sessions
+pub struct PlayerSave{
+    pub position: WorldPos,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub inventory: Inventory
+}
+
+pub struct WorldSave{
+    dir: PathBuf,
+    settings: WorldGenSettings,
+    blocks: BlockIdMap
+}
+
+impl WorldSave{
+    // opens the save in dir. a new save is created with the given
+    // settings, but an existing one keeps the settings it was made
+    // with so its terrain keeps lining up
+    pub fn open(dir: impl Into<PathBuf>, settings: WorldGenSettings) -> io::Result<Self>{
+        let dir = dir.into();
+        let (settings, block_names) = match fs::read(dir.join("world.meta")){
+            Ok(data) => {
+                let mut r = ByteReader::new(&data);
+                if r.read_bytes(4)? != META_MAGIC{
+                    return Err(invalid_data("world.meta is not a save file"));
+                }
+                let legacy_names = || LEGACY_BLOCK_NAMES.iter().map(|n| n.to_string()).collect();
+                match r.read_u32()?{
+                    // version 1 saves were all made with the default settings
+                    1 => (WorldGenSettings::legacy(), legacy_names()),
+                    2 => (read_settings(&mut r, 2)?, legacy_names()),
+                    3 => (read_settings(&mut r, 3)?, read_block_names(&mut r)?),
+                    SAVE_VERSION => (read_settings(&mut r, SAVE_VERSION)?, read_block_names(&mut r)?),
+                    version => return Err(invalid_data(&format!("save version {} is newer than this game", version)))
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (settings, Vec::new()),
+            Err(e) => return Err(e)
+        };
+        let blocks = BlockIdMap::new(block_names, &BLOCKS);
+        let save = Self{ dir, settings, blocks };
+        fs::create_dir_all(save.region_dir())?;
+        // migrates old saves and records any new blocks
+        save.write_meta()?;
+        Ok(save)
+    }
+
+    fn write_meta(&self) -> io::Result<()>{
+        let settings = self.settings.to_preset_string();
+        let mut w = ByteWriter::new();
+        w.write_bytes(META_MAGIC);
+        w.write_u32(SAVE_VERSION);
+        w.write_u32(settings.len() as u32);
+        w.write_bytes(settings.as_bytes());
+        w.write_u32(self.blocks.names().len() as u32);
+        for name in self.blocks.names(){
+            w.write_u16(name.len() as u16);
+            w.write_bytes(name.as_bytes());
+        }
+        write_atomic(&self.dir.join("world.meta"), &w.into_inner())
+    }
+
+    pub fn get_settings(&self) -> &WorldGenSettings{
+        &self.settings
+    }
+
+    fn region_dir(&self) -> PathBuf{
+        self.dir.join("region")
+    }
+
+    fn region_path(&self, region: RegionId) -> PathBuf{
+        self.region_dir().join(region.file_name())
+    }
+
+    // None if the chunk has never been saved. also returns the
+    // chunk's scheduled updates, in ticks from when it was saved
+    pub fn load_chunk(&self, id: ChunkId) -> io::Result<Option<(Chunk, ChunkUpdates)>>{
+        match region::read_chunk(&self.region_path(RegionId::of_chunk(id)), id)?{
+            Some(data) => Ok(Some(decode_chunk(id, &data, &self.blocks)?)),
+            None => Ok(None)
+        }
+    }
+
+    // each chunk is saved with its scheduled updates
+    pub fn save_chunks<'a>(&self, chunks: impl Iterator<Item = (&'a Chunk, &'a [(BlockPos, u32)])>) -> io::Result<()>{
+        // each region file is only rewritten once
+        let mut by_region: HashMap<RegionId, Vec<(ChunkId, Vec<u8>)>> = HashMap::new();
+        for (chunk, updates) in chunks{
+            let id = chunk.get_id();
+            by_region.entry(RegionId::of_chunk(id))
+                .or_default()
+                .push((id, encode_chunk(chunk, updates, &self.blocks)));
+        }
+        for (region, chunks) in by_region{
+            region::write_chunks(&self.region_path(region), &chunks)?;
+        }
+        Ok(())
+    }
+
+    // the pending blocks and the chunks that have been decorated.
+    // files from before decorated chunks were kept don't have them
+    pub fn load_pending(&self) -> io::Result<(PendingBlocks, HashSet<ChunkId>)>{
+        let (mut pending, mut decorated) = (HashMap::new(), HashSet::new());
+        let data = match fs::read(self.dir.join("pending.dat")){
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((pending, decorated)),
+            Err(e) => return Err(e)
+        };
+        let mut r = ByteReader::new(&data);
+        if r.read_bytes(4)? != PENDING_MAGIC{
+            return Err(invalid_data("pending.dat is not a pending block file"));
+        }
+        for _ in 0..r.read_u32()?{
+            let id = read_chunk_id(&mut r)?;
+            let count = r.read_u32()? as usize;
+            let mut blocks = HashMap::with_capacity(count);
+            for _ in 0..count{
+                let (x, y, z) = (r.read_u8()? as usize, r.read_u8()? as usize, r.read_u8()? as usize);
+                let local = LocalPos::new(x, y, z)
+                    .ok_or_else(|| invalid_data("pending block is outside of its chunk"))?;
+                let block = self.blocks.runtime_state(r.read_u32()?)?;
+                blocks.insert(BlockPos::from_local(id, local), block);
+            }
+            pending.insert(id, blocks);
+        }
+        if !r.is_done(){
+            for _ in 0..r.read_u32()?{
+                decorated.insert(read_chunk_id(&mut r)?);
+            }
+        }
+        Ok((pending, decorated))
+    }
+
+    // positions are stored relative to their chunk
+    pub fn save_pending(&self, pending: &PendingBlocks, decorated: &HashSet<ChunkId>) -> io::Result<()>{
+        let mut w = ByteWriter::new();
+        w.write_bytes(PENDING_MAGIC);
+        w.write_u32(pending.len() as u32);
+        for (id, blocks) in pending{
+            w.write_u32(id.x as u32);
+            w.write_u32(id.z as u32);
+            w.write_u32(blocks.len() as u32);
+            for (pos, block) in blocks{
+                let local = pos.to_local().unwrap();
+                w.write_u8(local.x as u8);
+                w.write_u8(local.y as u8);
+                w.write_u8(local.z as u8);
+                w.write_u32(self.blocks.save_state(*block));
+            }
+        }
+        w.write_u32(decorated.len() as u32);
+        for id in decorated{
+            w.write_u32(id.x as u32);
+            w.write_u32(id.z as u32);
+        }
+        write_atomic(&self.dir.join("pending.dat"), &w.into_inner())
+    }
+
+    // None if the player has never been saved
+    pub fn load_player(&self) -> io::Result<Option<PlayerSave>>{
+        let data = match fs::read(self.dir.join("player.dat")){
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e)
+        };
+        let mut r = ByteReader::new(&data);
+        if r.read_bytes(4)? != PLAYER_MAGIC{
+            return Err(invalid_data("player.dat is not a player file"));
+        }
+        let position = WorldPos::new(r.read_f32()?, r.read_f32()?, r.read_f32()?);
+        let (yaw, pitch) = (r.read_f32()?, r.read_f32()?);
+        let inventory = Inventory::read(&mut r, &self.blocks)?;
+        Ok(Some(PlayerSave{ position, yaw, pitch, inventory }))
+    }
+
+    pub fn save_player(&self, player: &PlayerSave) -> io::Result<()>{
+        let mut w = ByteWriter::new();
+        w.write_bytes(PLAYER_MAGIC);
+        w.write_f32(player.position.x);
+        w.write_f32(player.position.y);
+        w.write_f32(player.position.z);
+        w.write_f32(player.yaw);
+        w.write_f32(player.pitch);
+        player.inventory.write(&mut w, &self.blocks);
+        write_atomic(&self.dir.join("player.dat"), &w.into_inner())
+    }
+}
+
+fn read_chunk_id(r: &mut ByteReader) -> io::Result<ChunkId>{
+    Ok(ChunkId{ x: r.read_u32()? as i32, z: r.read_u32()? as i32 })
+}
+
+fn read_settings(r: &mut ByteReader, version: u32) -> io::Result<WorldGenSettings>{
+    let len = r.read_u32()? as usize;
+    let text = std::str::from_utf8(r.read_bytes(len)?)
+        .map_err(|_| invalid_data("world.meta settings aren't text"))?;
+    settings_from_text(text, version)
+}
+
+// before version 4 a key missing from the settings means the save
+// is older than that setting. biomes came with biome_scale
+fn settings_from_text(text: &str, version: u32) -> io::Result<WorldGenSettings>{
+    let mut settings = WorldGenSettings::default();
+    if version < 4{
+        let biomes = text.lines().any(|l| l.trim_start().starts_with("biome_scale"));
+        settings = WorldGenSettings{ biomes, ..WorldGenSettings::legacy() };
+    }
+    settings.apply(text)?;
+    Ok(settings)
+}
+
+fn read_block_names(r: &mut ByteReader) -> io::Result<Vec<String>>{
+    let count = r.read_u32()? as usize;
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count{
+        let len = r.read_u16()? as usize;
+        let name = std::str::from_utf8(r.read_bytes(len)?)
+            .map_err(|_| invalid_data("world.meta block names aren't text"))?;
+        names.push(name.to_string());
+    }
+    Ok(names)
+}
+
+// updates are stored relative to their chunk
+fn encode_chunk(chunk: &Chunk, updates: &[(BlockPos, u32)], ids: &BlockIdMap) -> Vec<u8>{
+    let mut w = ByteWriter::new();
+    w.write_u32(CHUNK_VERSION);
+    chunk.write(&mut w, ids);
+    w.write_u32(updates.len() as u32);
+    for (pos, delay) in updates{
+        let local = pos.to_local().unwrap();
+        w.write_u8(local.x as u8);
+        w.write_u8(local.y as u8);
+        w.write_u8(local.z as u8);
+        w.write_u32(*delay);
+    }
+    w.into_inner()
+}
+
+fn decode_chunk(id: ChunkId, data: &[u8], ids: &BlockIdMap) -> io::Result<(Chunk, ChunkUpdates)>{
+    let mut r = ByteReader::new(data);
+    match r.read_u32()?{
+        // from before updates were scheduled
+        1 => Ok((Chunk::read(id, &mut r, ids)?, Vec::new())),
+        CHUNK_VERSION => {
+            let chunk = Chunk::read(id, &mut r, ids)?;
+            let count = r.read_u32()? as usize;
+            let mut updates = Vec::with_capacity(count);
+            for _ in 0..count{
+                let (x, y, z) = (r.read_u8()? as usize, r.read_u8()? as usize, r.read_u8()? as usize);
+                let local = LocalPos::new(x, y, z)
+                    .ok_or_else(|| invalid_data("scheduled update is outside of its chunk"))?;
+                updates.push((BlockPos::from_local(id, local), r.read_u32()?));
+            }
+            Ok((chunk, updates))
+        },
+        version => Err(invalid_data(&format!("can't load chunk version {}", version)))
+    }
+}
+
+// writes to a temp file next to path and then swaps it in, so
+// a crash mid write leaves the old file as it was
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()>{
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::SAND_ID;
+
+    #[test]
+    fn chunks_keep_their_updates(){
+        let ids = BlockIdMap::new(Vec::new(), &BLOCKS);
+        let id = ChunkId{ x: -2, z: 5 };
+        let mut chunk = Chunk::empty(id);
+        chunk.set_block(3, 40, 9, *SAND_ID);
+        let pos = BlockPos::from_local(id, LocalPos{ x: 3, y: 40, z: 9 });
+        let data = encode_chunk(&chunk, &[(pos, 2)], &ids);
+        let (read, updates) = decode_chunk(id, &data, &ids).unwrap();
+        assert_eq!(read.get_block(3, 40, 9), *SAND_ID);
+        assert_eq!(updates, vec![(pos, 2)]);
+        // version 1 chunks are the same without the updates
+        let mut old = ByteWriter::new();
+        old.write_u32(1);
+        chunk.write(&mut old, &ids);
+        let (read, updates) = decode_chunk(id, &old.into_inner(), &ids).unwrap();
+        assert_eq!(read.get_block(3, 40, 9), *SAND_ID);
+        assert!(updates.is_empty());
+    }
+
+    #[test]
+    fn old_saves_keep_their_terrain(){
+        // saved before biomes, caves or the sea
+        let text = "seed = 4\noctaves = 5\ngain = 0.6\n";
+        let settings = settings_from_text(text, 2).unwrap();
+        assert_eq!(settings, WorldGenSettings{ seed: 4, ..WorldGenSettings::legacy() });
+        // saved with biomes, before caves
+        let text = "seed = 4\nsea_level = 20\nbiome_scale = 500\n";
+        let settings = settings_from_text(text, 2).unwrap();
+        assert!(settings.biomes && !settings.caves);
+        assert_eq!(settings.sea_level, 20);
+        // new saves write every setting out
+        let text = WorldGenSettings::legacy().to_preset_string();
+        assert_eq!(settings_from_text(&text, SAVE_VERSION).unwrap(), WorldGenSettings::legacy());
+    }
+}
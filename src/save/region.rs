@@ -0,0 +1,180 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use crate::coords::ChunkId;
+
+use super::bytes::{ByteReader, ByteWriter, invalid_data};
+use super::write_atomic;
+
+/*
+* A region file holds a REGION_WIDTH x REGION_WIDTH square of chunks.
+* Layout:
+* - magic b"OWRG"
+* - u32 region format version
+* - offset table, one (u32 offset, u32 length) per chunk slot.
+*   offsets are from the start of the file. a length of 0 means
+*   the chunk has never been saved
+* - the zlib compressed chunk payloads
+*/
+
+pub const REGION_WIDTH: i32 = 32;
+const CHUNKS_PER_REGION: usize = (REGION_WIDTH * REGION_WIDTH) as usize;
+const MAGIC: &[u8; 4] = b"OWRG";
+const REGION_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 4 + 4 + CHUNKS_PER_REGION * 8;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RegionId{
+    pub x: i32,
+    pub z: i32
+}
+
+impl RegionId{
+    pub fn of_chunk(id: ChunkId) -> Self{
+        Self{
+            x: id.x.div_euclid(REGION_WIDTH),
+            z: id.z.div_euclid(REGION_WIDTH)
+        }
+    }
+
+    pub fn file_name(&self) -> String{
+        format!("r.{}.{}.owr", self.x, self.z)
+    }
+}
+
+// where a chunk lives in its region's offset table
+fn slot(id: ChunkId) -> usize{
+    (id.z.rem_euclid(REGION_WIDTH) * REGION_WIDTH + id.x.rem_euclid(REGION_WIDTH)) as usize
+}
+
+// (offset, length) of every slot
+fn read_table(header: &[u8]) -> io::Result<Vec<(u32, u32)>>{
+    let mut r = ByteReader::new(header);
+    if r.read_bytes(4)? != MAGIC{
+        return Err(invalid_data("not a region file"));
+    }
+    let version = r.read_u32()?;
+    if version != REGION_VERSION{
+        return Err(invalid_data(&format!("unsupported region version {}", version)));
+    }
+    let mut table = Vec::with_capacity(CHUNKS_PER_REGION);
+    for _ in 0..CHUNKS_PER_REGION{
+        table.push((r.read_u32()?, r.read_u32()?));
+    }
+    Ok(table)
+}
+
+fn decompress(data: &[u8]) -> io::Result<Vec<u8>>{
+    let mut out = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+fn compress(data: &[u8]) -> io::Result<Vec<u8>>{
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+// reads and decompresses one chunk. only the header and that
+// chunk's payload are read from disk
+pub fn read_chunk(path: &Path, id: ChunkId) -> io::Result<Option<Vec<u8>>>{
+    let mut file = match File::open(path){
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e)
+    };
+    let mut header = vec![0u8; HEADER_SIZE];
+    file.read_exact(&mut header)?;
+    let (offset, len) = read_table(&header)?[slot(id)];
+    if len == 0{ return Ok(None); }
+
+    let mut payload = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(offset as u64))?;
+    file.read_exact(&mut payload)?;
+    Ok(Some(decompress(&payload)?))
+}
+
+// stores the given chunks in the region file, keeping every
+// other chunk already in it. the file is rewritten to a temp
+// file first so a crash mid write can't corrupt the region
+pub fn write_chunks(path: &Path, chunks: &[(ChunkId, Vec<u8>)]) -> io::Result<()>{
+    let mut payloads: Vec<Option<Vec<u8>>> = vec![None; CHUNKS_PER_REGION];
+    // a missing file is an empty region, but any other error would
+    // lose the chunks already in it if the file was rewritten
+    let existing = match fs::read(path){
+        Ok(d) => Some(d),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e)
+    };
+    if let Some(existing) = existing{
+        let table = read_table(&existing)?;
+        for (i, (offset, len)) in table.into_iter().enumerate(){
+            if len == 0{ continue; }
+            let (start, end) = (offset as usize, offset as usize + len as usize);
+            if end > existing.len(){
+                return Err(invalid_data("region offset table points past the end of the file"));
+            }
+            payloads[i] = Some(existing[start..end].to_vec());
+        }
+    }
+    for (id, data) in chunks{
+        payloads[slot(*id)] = Some(compress(data)?);
+    }
+
+    let mut w = ByteWriter::new();
+    w.write_bytes(MAGIC);
+    w.write_u32(REGION_VERSION);
+    let mut offset = HEADER_SIZE as u32;
+    for payload in &payloads{
+        match payload{
+            Some(p) => {
+                w.write_u32(offset);
+                w.write_u32(p.len() as u32);
+                offset += p.len() as u32;
+            },
+            None => {
+                w.write_u32(0);
+                w.write_u32(0);
+            }
+        }
+    }
+    for payload in payloads.iter().flatten(){
+        w.write_bytes(payload);
+    }
+
+    write_atomic(path, &w.into_inner())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn chunks_share_a_region_file(){
+        let dir = std::env::temp_dir().join(format!("openworld-region-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(RegionId::of_chunk(ChunkId{ x: -1, z: 0 }).file_name());
+        let _ = fs::remove_file(&path);
+        // the first and last slots of the region at (-1, 0)
+        let (a, b, c) = (ChunkId{ x: -32, z: 0 }, ChunkId{ x: -1, z: 31 }, ChunkId{ x: -5, z: 7 });
+        assert_eq!(read_chunk(&path, a).unwrap(), None);
+        write_chunks(&path, &[(a, vec![1; 500]), (b, vec![2, 3, 4])]).unwrap();
+        write_chunks(&path, &[(c, vec![5; 10])]).unwrap();
+        assert_eq!(read_chunk(&path, a).unwrap(), Some(vec![1; 500]));
+        assert_eq!(read_chunk(&path, b).unwrap(), Some(vec![2, 3, 4]));
+        assert_eq!(read_chunk(&path, c).unwrap(), Some(vec![5; 10]));
+        // rewriting one chunk leaves the others alone
+        write_chunks(&path, &[(b, vec![9; 2000])]).unwrap();
+        assert_eq!(read_chunk(&path, a).unwrap(), Some(vec![1; 500]));
+        assert_eq!(read_chunk(&path, b).unwrap(), Some(vec![9; 2000]));
+        assert_eq!(read_chunk(&path, c).unwrap(), Some(vec![5; 10]));
+        assert_eq!(read_chunk(&path, ChunkId{ x: -2, z: 0 }).unwrap(), None);
+        // nothing is left over from swapping the file in
+        assert!(!path.with_extension("tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::BTreeSet;
+
+use hashbrown::HashMap;
+
+use crate::blocks::{
+    block_of, BlockState, Chunk, SectionState,
+    AIR_ID, BLOCKS, CHUNK_WIDTH, DIRT_ID, GRASS_ID, LEAVES_ID, LOG_ID,
+    RenderShape, SECTION_HEIGHT, SECTIONS_PER_CHUNK, WATER_ID, WHEAT_ID
+};
+use crate::coords::{BlockPos, ChunkId, LocalPos};
+use crate::save::ChunkUpdates;
+use crate::world::World;
+use crate::worldgen::ChunkRng;
+
+// blocks picked in every section each tick
+pub const DEFAULT_RANDOM_TICK_SPEED: u32 = 3;
+// keeps ticks from rolling the same numbers as world generation
+const TICK_SALT: u64 = 3;
+// how far leaves look for a log before they decay
+const LEAF_SUPPORT_RADIUS: i32 = 4;
+// scheduled updates run each tick at most. the rest wait for
+// the next tick, so a big cascade is spread out
+pub const UPDATES_PER_TICK: usize = 256;
+// ticks between a falling block being woken and it moving
+const FALL_DELAY: u32 = 2;
+
+// picks which blocks get a random tick. every loaded section that
+// isn't empty gets speed picks each tick, so how often a block
+// changes doesn't depend on how many chunks are loaded
+pub struct RandomTicker{
+    rng: ChunkRng,
+    speed: u32
+}
+
+impl RandomTicker{
+    pub fn new(seed: u64, speed: u32) -> Self{
+        Self{ rng: ChunkRng::new(seed, ChunkId{ x: 0, z: 0 }, TICK_SALT), speed }
+    }
+
+    pub fn set_speed(&mut self, speed: u32){
+        self.speed = speed;
+    }
+
+    // the blocks to tick, each with a seed for whatever they do.
+    // chunks are gone through in order so the same seed always
+    // picks the same blocks
+    pub fn pick(&mut self, chunks: &HashMap<ChunkId, Chunk>) -> Vec<(BlockPos, u64)>{
+        let mut ids: Vec<ChunkId> = chunks.keys().copied().collect();
+        ids.sort_by_key(|id| (id.x, id.z));
+        let width = CHUNK_WIDTH as i32;
+        let height = SECTION_HEIGHT as i32;
+        let mut picked = Vec::new();
+        for id in ids{
+            let chunk = &chunks[&id];
+            for index in 0..SECTIONS_PER_CHUNK{
+                if chunk.get_section(index).get_state() == SectionState::Empty{ continue; }
+                for _ in 0..self.speed{
+                    let (x, y, z) = (self.rng.range(0, width), self.rng.range(0, height), self.rng.range(0, width));
+                    let local = LocalPos{ x: x as usize, y: index * SECTION_HEIGHT + y as usize, z: z as usize };
+                    picked.push((BlockPos::from_local(id, local), self.rng.next_u64()));
+                }
+            }
+        }
+        picked
+    }
+}
+
+// block updates waiting for their tick. a position is only in
+// the queue once, at the earliest tick it was scheduled for
+pub struct UpdateQueue{
+    // due tick then position, so updates come out in order
+    order: BTreeSet<(u64, i32, i32, i32)>,
+    // kept by chunk so saving or unloading one doesn't have to
+    // look through every update
+    due: HashMap<ChunkId, HashMap<BlockPos, u64>>
+}
+
+impl UpdateQueue{
+    pub fn new() -> Self{
+        Self{ order: BTreeSet::new(), due: HashMap::new() }
+    }
+
+    pub fn schedule(&mut self, pos: BlockPos, tick: u64){
+        let chunk = self.due.entry(pos.to_chunk_id()).or_default();
+        if let Some(old) = chunk.get(&pos).copied(){
+            if old <= tick{ return; }
+            self.order.remove(&(old, pos.x, pos.y, pos.z));
+        }
+        chunk.insert(pos, tick);
+        self.order.insert((tick, pos.x, pos.y, pos.z));
+    }
+
+    // takes up to budget of the updates due by tick, oldest first
+    pub fn pop_due(&mut self, tick: u64, budget: usize) -> Vec<BlockPos>{
+        let mut popped = Vec::new();
+        while popped.len() < budget{
+            match self.order.first().copied(){
+                Some((due, x, y, z)) if due <= tick => {
+                    self.order.remove(&(due, x, y, z));
+                    let pos = BlockPos::new(x, y, z);
+                    let id = pos.to_chunk_id();
+                    if let Some(chunk) = self.due.get_mut(&id){
+                        chunk.remove(&pos);
+                        if chunk.is_empty(){ self.due.remove(&id); }
+                    }
+                    popped.push(pos);
+                },
+                _ => break
+            }
+        }
+        popped
+    }
+
+    // removes a chunk's updates, as ticks from now
+    pub fn take_chunk(&mut self, id: ChunkId, tick: u64) -> ChunkUpdates{
+        let updates = self.in_chunk(id, tick);
+        for (pos, due) in self.due.remove(&id).unwrap_or_default(){
+            self.order.remove(&(due, pos.x, pos.y, pos.z));
+        }
+        updates
+    }
+
+    // a chunk's updates, as ticks from now
+    pub fn in_chunk(&self, id: ChunkId, tick: u64) -> ChunkUpdates{
+        let mut updates: ChunkUpdates = match self.due.get(&id){
+            Some(chunk) => chunk.iter()
+                .map(|(pos, due)| (*pos, due.saturating_sub(tick) as u32))
+                .collect(),
+            None => return Vec::new()
+        };
+        updates.sort_by_key(|(pos, delay)| (*delay, pos.x, pos.y, pos.z));
+        updates
+    }
+}
+
+// how long after a block or its neighbor changes that it should
+// get a scheduled update. None for blocks that don't care
+pub fn update_delay(state: usize) -> Option<u32>{
+    if BLOCKS.of_state(state).falls{
+        return Some(FALL_DELAY);
+    }
+    None
+}
+
+// a block falling through here would push it out of the way
+fn can_fall_into(state: usize) -> bool{
+    state == AIR_ID
+        || block_of(state) == *WATER_ID
+        || BLOCKS.of_state(state).shape == RenderShape::Cross
+}
+
+// runs the block at pos's scheduled update
+pub fn scheduled_update(world: &mut World, pos: BlockPos){
+    let state = match world.get_block(pos){
+        Some(s) => s,
+        None => return
+    };
+    if BLOCKS.of_state(state).falls{
+        let below = pos.offset(0, -1, 0);
+        // one block at a time. moving wakes the block up again,
+        // along with anything that was sitting on it
+        if world.get_block(below).is_some_and(can_fall_into){
+            world.set_block(pos, AIR_ID);
+            world.set_block(below, state);
+        }
+    }
+}
+
+// runs the block at pos's random tick behavior, if it has one
+pub fn random_tick(world: &mut World, pos: BlockPos, seed: u64){
+    let state = match world.get_block(pos){
+        Some(s) => s,
+        None => return
+    };
+    let mut rng = ChunkRng::new(seed, pos.to_chunk_id(), TICK_SALT);
+    let block = block_of(state);
+    if block == *GRASS_ID{
+        grass_tick(world, pos, &mut rng);
+    }else if block == *WHEAT_ID{
+        crop_tick(world, pos, state);
+    }else if block == *LEAVES_ID{
+        leaves_tick(world, pos);
+    }
+}
+
+// there is no light yet, so a block counts as lit as long
+// as nothing solid is sitting right on top of it
+fn is_lit(world: &World, pos: BlockPos) -> bool{
+    match world.get_block(pos.offset(0, 1, 0)){
+        Some(above) => !BLOCKS.of_state(above).is_opaque_cube(),
+        None => true
+    }
+}
+
+// grass dies when it is covered, and otherwise spreads to lit
+// dirt close to it. it can climb up a block or fall down three
+fn grass_tick(world: &mut World, pos: BlockPos, rng: &mut ChunkRng){
+    if !is_lit(world, pos){
+        world.set_block(pos, *DIRT_ID);
+        return;
+    }
+    let target = pos.offset(rng.range(-1, 2), rng.range(-3, 2), rng.range(-1, 2));
+    if world.get_block(target) == Some(*DIRT_ID) && is_lit(world, target){
+        world.set_block(target, *GRASS_ID);
+    }
+}
+
+fn crop_tick(world: &mut World, pos: BlockPos, state: usize){
+    let state = BlockState::from_id(state);
+    if state.age < BLOCKS.get(state.block).max_age{
+        world.set_block(pos, state.with_age(state.age + 1).id());
+    }
+}
+
+// leaves fall apart once there's no log near them. leaves next
+// to a chunk that isn't loaded wait, since it might have one
+fn leaves_tick(world: &mut World, pos: BlockPos){
+    let r = LEAF_SUPPORT_RADIUS;
+    for dx in -r..=r{
+        for dy in -r..=r{
+            for dz in -r..=r{
+                match world.get_block(pos.offset(dx, dy, dz)){
+                    Some(b) if block_of(b) == *LOG_ID => return,
+                    None if pos.offset(dx, dy, dz).to_local().is_some() => return,
+                    _ => {}
+                }
+            }
+        }
+    }
+    world.set_block(pos, AIR_ID);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::{CHUNK_HEIGHT, SAND_ID};
+    use crate::worldgen::WorldGenSettings;
+
+    // one flat chunk, and the top of the ground in the middle of
+    // it with anything growing on it cleared away
+    fn flat_world(seed: u64) -> (World, BlockPos){
+        let mut settings = WorldGenSettings::load_preset("flat").unwrap();
+        settings.seed = seed;
+        let mut world = World::new(0, settings, None);
+        world.update(ChunkId{ x: 0, z: 0 });
+        let mut surface = BlockPos::new(8, CHUNK_HEIGHT as i32 - 1, 8);
+        while !BLOCKS.of_state(world.get_block(surface).unwrap()).is_opaque_cube()
+            || [*LEAVES_ID, *LOG_ID].contains(&block_of(world.get_block(surface).unwrap())){
+            surface = surface.offset(0, -1, 0);
+        }
+        for y in 1..CHUNK_HEIGHT as i32 - surface.y{
+            world.set_block(surface.offset(0, y, 0), AIR_ID);
+        }
+        (world, surface)
+    }
+
+    #[test]
+    fn crops_grow_up_to_their_max_age(){
+        let (mut world, surface) = flat_world(1);
+        let crop = surface.offset(0, 1, 0);
+        world.set_block(crop, *WHEAT_ID);
+        for _ in 0..20{
+            random_tick(&mut world, crop, 0);
+        }
+        let state = BlockState::from_id(world.get_block(crop).unwrap());
+        assert_eq!(state.block, *WHEAT_ID);
+        assert_eq!(state.age, BLOCKS.get(*WHEAT_ID).max_age);
+    }
+
+    #[test]
+    fn leaves_need_a_log(){
+        let (mut world, surface) = flat_world(1);
+        // high enough that no tree can reach
+        let log = surface.offset(0, 27, 0);
+        let leaves = surface.offset(0, 30, 0);
+        world.set_block(log, *LOG_ID);
+        world.set_block(leaves, *LEAVES_ID);
+        random_tick(&mut world, leaves, 0);
+        assert_eq!(world.get_block(leaves), Some(*LEAVES_ID));
+        world.set_block(log, AIR_ID);
+        random_tick(&mut world, leaves, 0);
+        assert_eq!(world.get_block(leaves), Some(AIR_ID));
+    }
+
+    #[test]
+    fn queue_keeps_the_earliest_tick(){
+        let mut queue = UpdateQueue::new();
+        let (a, b) = (BlockPos::new(0, 5, 0), BlockPos::new(-20, 5, 3));
+        queue.schedule(a, 10);
+        queue.schedule(a, 4);
+        queue.schedule(a, 8);
+        queue.schedule(b, 4);
+        assert!(queue.pop_due(3, 10).is_empty());
+        // only one fits in the budget, the other waits
+        assert_eq!(queue.pop_due(4, 1), vec![b]);
+        assert_eq!(queue.in_chunk(ChunkId{ x: -2, z: 0 }, 2), Vec::new());
+        assert_eq!(queue.take_chunk(ChunkId{ x: 0, z: 0 }, 2), vec![(a, 2)]);
+        assert!(queue.pop_due(100, 10).is_empty());
+        // chunks with nothing left aren't kept around
+        assert!(queue.due.is_empty());
+    }
+
+    #[test]
+    fn sand_falls_to_the_ground(){
+        let (mut world, surface) = flat_world(1);
+        world.set_random_tick_speed(0);
+        let column: Vec<BlockPos> = (10..13).map(|y| surface.offset(0, y, 0)).collect();
+        for pos in &column{
+            world.set_block(*pos, *SAND_ID);
+        }
+        for _ in 0..40{
+            world.tick();
+        }
+        for y in 1..4{
+            assert_eq!(world.get_block(surface.offset(0, y, 0)), Some(*SAND_ID));
+        }
+        assert_eq!(world.get_block(column[0]), Some(AIR_ID));
+    }
+
+    #[test]
+    fn ticks_are_the_same_for_a_seed(){
+        // a dirt patch in the grass, which fills back in over time
+        let run = |seed: u64| -> Vec<Option<usize>>{
+            let (mut world, surface) = flat_world(seed);
+            let patch: Vec<BlockPos> = (-1..=1).flat_map(|x| (-1..=1).map(move |z| surface.offset(x, 0, z))).collect();
+            for pos in &patch{
+                world.set_block(*pos, *DIRT_ID);
+            }
+            // about one pick per block each tick
+            world.set_random_tick_speed(4096);
+            for _ in 0..20{
+                world.tick();
+            }
+            patch.iter().map(|pos| world.get_block(*pos)).collect()
+        };
+        let a = run(7);
+        assert_eq!(a, run(7));
+        assert!(a.contains(&Some(*GRASS_ID)));
+    }
+}
\ No newline at end of file
@@ -1,13 +1,29 @@
 use instant::Instant;
 
+// simulation ticks a second
+pub const TICK_RATE: u32 = 60;
+pub const TICK_DT: f32 = 1.0 / TICK_RATE as f32;
+// the most ticks run in one frame. after a stall the rest are
+// dropped instead of making the next frames stall too
+pub const MAX_TICKS_PER_FRAME: u32 = 5;
+
 pub struct TimeKeep{
-    last_tick: Instant
+    last_tick: Instant,
+    // time waiting to be simulated, always less than a tick
+    // once the frame's ticks are taken
+    accumulator: f32,
+    paused: bool,
+    // how fast simulated time goes compared to real time
+    time_scale: f32
 }
 
 impl TimeKeep{
     pub fn new() -> Self{
         Self {
-            last_tick: Instant::now()
+            last_tick: Instant::now(),
+            accumulator: 0.0,
+            paused: false,
+            time_scale: 1.0
          }
     }
 
@@ -17,4 +33,65 @@ impl TimeKeep{
         self.last_tick = new_tick;
         dt.as_secs_f32()
     }
+
+    // adds dt seconds of real time and takes as many whole
+    // ticks out of it as there are, up to the cap
+    pub fn advance(&mut self, dt: f32) -> u32{
+        if self.paused{ return 0; }
+        self.accumulator += dt * self.time_scale;
+        let ticks = (self.accumulator / TICK_DT) as u32;
+        self.accumulator -= ticks as f32 * TICK_DT;
+        ticks.min(MAX_TICKS_PER_FRAME)
+    }
+
+    // how far between the last tick and the next one the frame
+    // is, from 0 to 1
+    pub fn get_alpha(&self) -> f32{
+        (self.accumulator / TICK_DT).clamp(0.0, 1.0)
+    }
+
+    // time stops, but frames keep being drawn
+    pub fn toggle_pause(&mut self){
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool{
+        self.paused
+    }
+
+    // 2 runs twice as fast, 0.5 half as fast
+    pub fn set_time_scale(&mut self, scale: f32){
+        self.time_scale = scale.max(0.0);
+    }
+
+    pub fn get_time_scale(&self) -> f32{
+        self.time_scale
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn leftover_time_carries_over(){
+        let mut time = TimeKeep::new();
+        assert_eq!(time.advance(TICK_DT * 0.5), 0);
+        assert!((time.get_alpha() - 0.5).abs() < 0.001);
+        assert_eq!(time.advance(TICK_DT * 2.0), 2);
+        assert!((time.get_alpha() - 0.5).abs() < 0.001);
+        // a long stall only catches up so far
+        assert_eq!(time.advance(10.0), MAX_TICKS_PER_FRAME);
+        assert_eq!(time.advance(0.0), 0);
+    }
+
+    #[test]
+    fn pausing_and_scaling(){
+        let mut time = TimeKeep::new();
+        time.toggle_pause();
+        assert_eq!(time.advance(1.0), 0);
+        time.toggle_pause();
+        time.set_time_scale(0.5);
+        assert_eq!(time.advance(TICK_DT * 4.5), 2);
+    }
 }
\ No newline at end of file
@@ -1,68 +1,539 @@
-use bracket_noise::prelude::{
-    FastNoise,
-    NoiseType,
-    FractalType
+use std::collections::VecDeque;
+use std::io;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::blocks::{
+    Chunk, ChunkNeighbors, SectionId, SectionState, BLOCKS,
+    CHUNK_HEIGHT, CHUNK_WIDTH, SECTION_HEIGHT, SECTIONS_PER_CHUNK
+};
+use crate::coords::{BlockPos, ChunkId};
+use crate::direction::AbsolouteDirection;
+use crate::save::{ChunkUpdates, PendingBlocks, PlayerSave, WorldSave};
+use crate::worldgen::{can_replace, Biome, WorldGenerator, WorldGenSettings};
+use crate::physics::{any_overlapping, guard_edges, sweep_with_step, Collision, PhysicsObject};
+use crate::raycast::{raycast, Ray, RayHit, RaycastSettings};
+use crate::ticking::{
+    random_tick, scheduled_update, update_delay,
+    RandomTicker, UpdateQueue, DEFAULT_RANDOM_TICK_SPEED, UPDATES_PER_TICK
 };
-use hashbrown::HashMap;
-
-use crate::blocks::{Chunk, ChunkId, CHUNK_WIDTH, world_coord_to_chunk_id};
-use crate::physics::PhysicsObject;
-use crate::entity::Entity;
-use crate::render::voxel::{VoxelMesh, INVERSE_VOXEL_WIDTH};
-
-const WORLD_WIDTH: u32 = 16;
-const WORLD_DEPTH: u32 = 16;
-
-fn chunk_loader(width: u32, depth: u32, height_map: &FastNoise, device: &wgpu::Device) -> HashMap<ChunkId, Chunk>{
-    let mut chunks: HashMap<ChunkId, Chunk> = HashMap::with_capacity((width*depth) as usize);
-    let half_width = width / 2;
-    let half_depth = depth / 2;
-    for x in 0..width{
-        for z in 0..depth{
-            let id = ChunkId { x: x as i32- half_width as i32, z: z as i32 - half_depth as i32 };
-            chunks.insert(id, Chunk::flat_world(id, height_map, device));
-        }
-    } 
-    chunks
+
+// how many chunks out from the player's chunk are kept loaded
+pub const DEFAULT_RENDER_DISTANCE: i32 = 8;
+// generating a chunk isn't free, so only this many are
+// made each frame. the rest wait in the load queue
+const CHUNK_LOADS_PER_FRAME: usize = 8;
+
+fn in_range(id: ChunkId, center: ChunkId, render_distance: i32) -> bool{
+    (id.x - center.x).abs() <= render_distance && (id.z - center.z).abs() <= render_distance
+}
+
+// the chunks that were loaded and unloaded since the last
+// time they were taken. the renderer uses this to know which
+// meshes to build and which to free
+#[derive(Default)]
+pub struct ChunkChanges{
+    pub loaded: Vec<ChunkId>,
+    pub unloaded: Vec<ChunkId>,
+    // sections of already loaded chunks whose meshes are out of
+    // date, ex: a block was set or a neighbor loaded and now
+    // their border faces can be culled
+    pub dirty: HashSet<SectionId>
+}
+
+impl ChunkChanges{
+    fn mark_dirty(&mut self, section: SectionId){
+        // newly loaded chunks get a full mesh anyways
+        if !self.loaded.contains(&section.chunk){
+            self.dirty.insert(section);
+        }
+    }
 }
 
 pub struct World{
     chunks: HashMap<ChunkId, Chunk>,
-    height_map: FastNoise
+    generator: WorldGenerator,
+    render_distance: i32,
+    // the chunk the player was in during the last update
+    center: Option<ChunkId>,
+    // chunks waiting to be generated, closest first
+    load_queue: VecDeque<ChunkId>,
+    changes: ChunkChanges,
+    // decoration blocks waiting for their chunk to be loaded
+    pending: PendingBlocks,
+    // chunks that have already handed out the decoration blocks
+    // that spill into their neighbors. chunks that weren't saved
+    // are generated again, and would hand them out again
+    decorated: HashSet<ChunkId>,
+    random_ticker: RandomTicker,
+    scheduled: UpdateQueue,
+    // game ticks since the world was loaded
+    tick_count: u64,
+    // where changed chunks are written. None means nothing is saved
+    save: Option<WorldSave>
 }
 
 impl World{
-    pub fn new(device: &wgpu::Device) -> Self{
-        let mut height_map = FastNoise::seeded(10);
-        height_map.set_noise_type(NoiseType::PerlinFractal);
-        height_map.set_fractal_type(FractalType::FBM);
-        height_map.set_fractal_octaves(5);
-        height_map.set_fractal_gain(0.6);
-        height_map.set_fractal_lacunarity(2.0);
-        height_map.set_frequency(2.0);
-
+    pub fn new(render_distance: i32, settings: WorldGenSettings, save: Option<WorldSave>) -> Self{
+        let (pending, decorated) = match save.as_ref().map(|s| s.load_pending()){
+            Some(Ok(pending)) => pending,
+            Some(Err(e)) => {
+                eprintln!("Failed to load pending decorations: {}", e);
+                (HashMap::new(), HashSet::new())
+            },
+            None => (HashMap::new(), HashSet::new())
+        };
+        let random_ticker = RandomTicker::new(settings.seed, DEFAULT_RANDOM_TICK_SPEED);
         Self{
-            chunks: chunk_loader(WORLD_WIDTH, WORLD_DEPTH, &height_map, device),
-            height_map
+            chunks: HashMap::new(),
+            generator: WorldGenerator::new(settings),
+            render_distance,
+            center: None,
+            load_queue: VecDeque::new(),
+            changes: ChunkChanges::default(),
+            pending,
+            decorated,
+            random_ticker,
+            scheduled: UpdateQueue::new(),
+            tick_count: 0,
+            save
+        }
+    }
+
+    // moves the loaded area so it is centered on player_chunk.
+    // should be called once a frame
+    pub fn update(&mut self, player_chunk: ChunkId){
+        if self.center != Some(player_chunk){
+            self.center = Some(player_chunk);
+            self.unload_far_chunks();
+            self.queue_missing_chunks();
+        }
+
+        for _ in 0..CHUNK_LOADS_PER_FRAME{
+            let id = match self.load_queue.pop_front(){
+                Some(id) => id,
+                None => break
+            };
+            if self.chunks.contains_key(&id){ continue; }
+            let (chunk, overflow, updates) = self.load_or_generate(id);
+            self.chunks.insert(id, chunk);
+            for (pos, delay) in updates{
+                self.scheduled.schedule(pos, self.tick_count + delay as u64);
+            }
+            self.changes.loaded.push(id);
+            // trees from chunks that were made before this one
+            for (pos, block) in self.pending.remove(&id).unwrap_or_default(){
+                self.place_decoration(pos, block);
+            }
+            // the neighbors already have them, or they're waiting
+            // in pending
+            if self.decorated.insert(id){
+                for (pos, block) in overflow{
+                    self.place_decoration(pos, block);
+                }
+            }
+            // the neighbors drew faces along the border with this
+            // chunk since they didn't know what was here
+            self.mark_neighbors_dirty(id);
+        }
+    }
+
+    // the border faces of the chunks around id are out of date
+    fn mark_neighbors_dirty(&mut self, id: ChunkId){
+        for neighbor in id.neighbors(){
+            if let Some(chunk) = self.chunks.get(&neighbor){
+                for index in 0..SECTIONS_PER_CHUNK{
+                    // empty sections have nothing to cull
+                    if chunk.get_section(index).get_state() != SectionState::Empty{
+                        self.changes.mark_dirty(SectionId::new(neighbor, index));
+                    }
+                }
+            }
+        }
+    }
+
+    // runs one game tick of block behavior, ex: grass spreading
+    // and sand falling
+    pub fn tick(&mut self){
+        self.tick_count += 1;
+        for pos in self.scheduled.pop_due(self.tick_count, UPDATES_PER_TICK){
+            scheduled_update(self, pos);
+        }
+        for (pos, seed) in self.random_ticker.pick(&self.chunks){
+            random_tick(self, pos, seed);
+        }
+    }
+
+    // gives the block at pos an update in delay ticks. does
+    // nothing if its chunk isn't loaded
+    pub fn schedule_update(&mut self, pos: BlockPos, delay: u32){
+        let chunk = match self.chunks.get_mut(&pos.to_chunk_id()){
+            Some(c) => c,
+            None => return
+        };
+        // the update has to be saved along with the chunk
+        chunk.set_modified(true);
+        self.scheduled.schedule(pos, self.tick_count + delay as u64);
+    }
+
+    // lets the block at pos and the blocks touching it react
+    // to a change
+    fn wake_neighbors(&mut self, pos: BlockPos){
+        let sides = [
+            AbsolouteDirection::North, AbsolouteDirection::East, AbsolouteDirection::South,
+            AbsolouteDirection::West, AbsolouteDirection::Up, AbsolouteDirection::Down
+        ];
+        for target in std::iter::once(pos).chain(sides.map(|d| pos.neighbor(d))){
+            if let Some(delay) = self.get_block(target).and_then(update_delay){
+                self.schedule_update(target, delay);
+            }
+        }
+    }
+
+    // chunks that have been saved are read back in, everything
+    // else is generated and decorated. also returns the decoration
+    // blocks that spilled out into other chunks, and the chunk's
+    // scheduled updates
+    fn load_or_generate(&self, id: ChunkId) -> (Chunk, Vec<(BlockPos, usize)>, ChunkUpdates){
+        if let Some(save) = &self.save{
+            match save.load_chunk(id){
+                Ok(Some((chunk, updates))) => return (chunk, Vec::new(), updates),
+                Ok(None) => {},
+                Err(e) => eprintln!("Failed to load chunk {:?}, regenerating it: {}", id, e)
+            }
+        }
+        let mut chunk = self.generator.generate(id);
+        let overflow = self.generator.decorate(&mut chunk);
+        (chunk, overflow, Vec::new())
+    }
+
+    // puts a decoration block from another chunk's features into
+    // the world, or holds on to it until its chunk is loaded.
+    // the chunk is marked modified since generating it again
+    // won't bring the block back
+    fn place_decoration(&mut self, pos: BlockPos, block: usize){
+        let id = pos.to_chunk_id();
+        match self.get_block(pos){
+            Some(existing) => if can_replace(existing, block){
+                self.set_block(pos, block);
+            },
+            None => {
+                self.pending.entry(id).or_default().insert(pos, block);
+            }
+        }
+    }
+
+    fn unload_far_chunks(&mut self){
+        let center = match self.center{
+            Some(c) => c,
+            None => return
+        };
+        let far: Vec<ChunkId> = self.chunks.keys()
+            .filter(|id| !in_range(**id, center, self.render_distance))
+            .copied()
+            .collect();
+        let mut to_save: Vec<(Chunk, ChunkUpdates)> = Vec::new();
+        for id in far.iter().copied(){
+            let chunk = self.chunks.remove(&id).unwrap();
+            let updates = self.scheduled.take_chunk(id, self.tick_count);
+            if chunk.is_modified(){ to_save.push((chunk, updates)); }
+            // a chunk that was loaded and dropped before anyone saw it
+            // doesn't need to be reported at all
+            match self.changes.loaded.iter().position(|l| *l == id){
+                Some(i) => { self.changes.loaded.swap_remove(i); },
+                None => self.changes.unloaded.push(id)
+            }
+            self.changes.dirty.retain(|d| d.chunk != id);
+        }
+        // faces that were culled against the unloaded chunks
+        // have to be drawn again
+        for id in far{
+            self.mark_neighbors_dirty(id);
+        }
+        if let (Some(save), false) = (&self.save, to_save.is_empty()){
+            if let Err(e) = save.save_chunks(to_save.iter().map(|(c, u)| (c, u.as_slice()))){
+                eprintln!("Failed to save unloaded chunks: {}", e);
+            }
+            // the saved chunks may hold decorations from chunks that
+            // weren't saved. those can't be handed out again
+            if let Err(e) = save.save_pending(&self.pending, &self.decorated){
+                eprintln!("Failed to save pending decorations: {}", e);
+            }
+        }
+    }
+
+    // writes every changed chunk that is still loaded
+    pub fn save_all(&mut self) -> io::Result<()>{
+        let save = match &self.save{
+            Some(s) => s,
+            None => return Ok(())
+        };
+        let to_save: Vec<(&Chunk, ChunkUpdates)> = self.chunks.values()
+            .filter(|c| c.is_modified())
+            .map(|c| (c, self.scheduled.in_chunk(c.get_id(), self.tick_count)))
+            .collect();
+        save.save_chunks(to_save.iter().map(|(c, u)| (*c, u.as_slice())))?;
+        save.save_pending(&self.pending, &self.decorated)?;
+        for chunk in self.chunks.values_mut(){
+            chunk.set_modified(false);
+        }
+        Ok(())
+    }
+
+    // None if there is no save or the player was never saved
+    pub fn load_player(&self) -> Option<PlayerSave>{
+        match self.save.as_ref()?.load_player(){
+            Ok(player) => player,
+            Err(e) => {
+                eprintln!("Failed to load the player: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn save_player(&self, player: &PlayerSave) -> io::Result<()>{
+        match &self.save{
+            Some(save) => save.save_player(player),
+            None => Ok(())
+        }
+    }
+
+    fn queue_missing_chunks(&mut self){
+        let center = match self.center{
+            Some(c) => c,
+            None => return
+        };
+        let rd = self.render_distance;
+        let mut missing: Vec<ChunkId> = Vec::new();
+        for x in center.x-rd..=center.x+rd{
+            for z in center.z-rd..=center.z+rd{
+                let id = ChunkId{ x, z };
+                if !self.chunks.contains_key(&id){
+                    missing.push(id);
+                }
+            }
+        }
+        // load the chunks closest to the player first
+        missing.sort_by_key(|id| (id.x - center.x).pow(2) + (id.z - center.z).pow(2));
+        self.load_queue = missing.into();
+    }
+
+    // the biome of the column at block x, z. works whether
+    // or not the chunk is loaded
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome{
+        self.generator.biome_at(x, z)
+    }
+
+    // None if the block is in a chunk that isn't loaded
+    // or is above or below the world
+    pub fn get_block(&self, pos: BlockPos) -> Option<usize>{
+        let local = pos.to_local()?;
+        Some(self.chunks.get(&pos.to_chunk_id())?.get_block(local.x, local.y, local.z))
+    }
+
+    // the first block along the ray. unloaded chunks are
+    // treated as empty
+    pub fn raycast(&self, ray: &Ray, settings: &RaycastSettings) -> Option<RayHit>{
+        raycast(ray, settings, |pos| self.get_block(pos))
+    }
+
+    // returns false if the block couldn't be set because its
+    // chunk isn't loaded or it is above or below the world
+    pub fn set_block(&mut self, pos: BlockPos, block: usize) -> bool{
+        let (x, y, z) = match pos.to_local(){
+            Some(local) => (local.x, local.y, local.z),
+            None => return false
+        };
+        let id = pos.to_chunk_id();
+        let chunk = match self.chunks.get_mut(&id){
+            Some(c) => c,
+            None => return false
+        };
+        if chunk.get_block(x, y, z) == block{ return true; }
+        chunk.set_block(x, y, z, block);
+        chunk.set_modified(true);
+        self.wake_neighbors(pos);
+
+        let section = y / SECTION_HEIGHT;
+        self.changes.mark_dirty(SectionId::new(id, section));
+        // blocks on the edge of a section can change which
+        // faces are culled in the section next to them
+        let last = CHUNK_WIDTH - 1;
+        let [north, south, east, west] = id.neighbors();
+        let mut touching: Vec<SectionId> = Vec::new();
+        if x == 0{ touching.push(SectionId::new(west, section)); }
+        if x == last{ touching.push(SectionId::new(east, section)); }
+        if z == 0{ touching.push(SectionId::new(south, section)); }
+        if z == last{ touching.push(SectionId::new(north, section)); }
+        if y % SECTION_HEIGHT == 0 && section > 0{
+            touching.push(SectionId::new(id, section - 1));
         }
+        if y % SECTION_HEIGHT == SECTION_HEIGHT - 1 && section + 1 < SECTIONS_PER_CHUNK{
+            touching.push(SectionId::new(id, section + 1));
+        }
+        for t in touching{
+            if self.chunks.contains_key(&t.chunk){
+                self.changes.mark_dirty(t);
+            }
+        }
+        true
     }
 
     // getters
-    pub fn get_chunk_meshes(&self) -> Vec<&VoxelMesh>{
-        let mut v = Vec::with_capacity(self.chunks.len());
-        v.extend(
-            self.chunks.iter().map(|(_, chunk)| &chunk.mesh)
-        );
-        return v
-    }
-
-    pub fn check_col<O: PhysicsObject>(&self, obj: &O){
-        let pd = obj.get_data();
-        let c_id = world_coord_to_chunk_id(pd.position.x, pd.position.z);
-        let c_x = pd.position.x * INVERSE_VOXEL_WIDTH - c_id.x as f32 * CHUNK_WIDTH as f32;
-        let c_z = pd.position.z * INVERSE_VOXEL_WIDTH - c_id.z as f32 * CHUNK_WIDTH as f32;
-        println!("WC: {:?}", pd.position);
-        println!("CID: {:?}", c_id);
-        println!("CC: {}, {}", c_x, c_z);
+    pub fn get_center(&self) -> Option<ChunkId>{
+        self.center
+    }
+
+    pub fn get_chunk(&self, id: &ChunkId) -> Option<&Chunk>{
+        self.chunks.get(id)
+    }
+
+    pub fn get_neighbors(&self, id: &ChunkId) -> Option<ChunkNeighbors<'_>>{
+        let [north, south, east, west] = id.neighbors();
+        Some(ChunkNeighbors{
+            center: self.chunks.get(id)?,
+            north: self.chunks.get(&north),
+            south: self.chunks.get(&south),
+            east: self.chunks.get(&east),
+            west: self.chunks.get(&west)
+        })
+    }
+
+    // setters
+    pub fn set_render_distance(&mut self, render_distance: i32){
+        self.render_distance = render_distance;
+        // force the next update to recalculate what is loaded
+        self.center = None;
+    }
+
+    // blocks picked in each section every tick. 0 turns
+    // random ticks off
+    pub fn set_random_tick_speed(&mut self, speed: u32){
+        self.random_ticker.set_speed(speed);
+    }
+
+    // hands over everything loaded or unloaded since the last call
+    pub fn take_chunk_changes(&mut self) -> ChunkChanges{
+        std::mem::take(&mut self.changes)
+    }
+
+    // moves obj by movement, stopping it against solid blocks
+    // and stepping it up low ledges
+    pub fn check_col<O: PhysicsObject>(&self, obj: &mut O, movement: cgmath::Vector3<f32>) -> Collision{
+        let (position, collision) = sweep_with_step(obj.get_data(), movement, |pos| self.is_solid(pos));
+        obj.update_position(position);
+        collision
+    }
+
+    // lets obj fall for dt seconds, unless it's climbing, then
+    // moves it by its speed. whatever it runs into stops it
+    pub fn simulate<O: PhysicsObject>(&self, obj: &mut O, dt: f32) -> Collision{
+        let data = obj.get_data_mut();
+        data.climbing = any_overlapping(data, |pos| self.get_block(pos).is_some_and(|b| BLOCKS.of_state(b).climbable));
+        if !data.climbing{ data.apply_gravity(dt); }
+        let movement = guard_edges(data, data.force * dt, |pos| self.is_solid(pos));
+        let collision = self.check_col(obj, movement);
+        obj.get_data_mut().apply_collision(collision);
+        collision
+    }
+
+    // chunks that aren't loaded yet are solid so nothing falls
+    // out of the world while they load. so is everything below
+    // it, but not the sky above it
+    fn is_solid(&self, pos: BlockPos) -> bool{
+        match self.get_block(pos){
+            Some(block) => BLOCKS.of_state(block).solid,
+            None => pos.y < CHUNK_HEIGHT as i32
+        }
+    }
+}
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::{AIR_ID, LEAVES_ID};
+
+    #[test]
+    fn broken_decorations_stay_broken(){
+        let dir = std::env::temp_dir().join(format!("openworld-decorations-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let settings = WorldGenSettings::default();
+        // a leaf that a tree spills into the air of the next chunk over
+        let generator = WorldGenerator::new(settings.clone());
+        let (source, leaf) = (-8..8).flat_map(|x| (-8..8).map(move |z| ChunkId{ x, z }))
+            .find_map(|id| {
+                let overflow = generator.decorate(&mut generator.generate(id));
+                let (pos, _) = overflow.into_iter().find(|(pos, block)| {
+                    let local = pos.to_local().unwrap();
+                    *block == *LEAVES_ID && generator.generate(pos.to_chunk_id()).get_block(local.x, local.y, local.z) == AIR_ID
+                })?;
+                Some((id, pos))
+            })
+            .expect("no trees near a chunk border");
+        let neighbor = leaf.to_chunk_id();
+
+        let open = || World::new(0, settings.clone(), Some(WorldSave::open(&dir, settings.clone()).unwrap()));
+        let mut world = open();
+        world.update(source);
+        world.update(neighbor);
+        assert_eq!(world.get_block(leaf), Some(*LEAVES_ID));
+        world.set_block(leaf, AIR_ID);
+        // the neighbor is saved, the source is made again from scratch
+        world.update(source);
+        world.update(neighbor);
+        assert_eq!(world.get_block(leaf), Some(AIR_ID));
+        world.save_all().unwrap();
+
+        let mut world = open();
+        world.update(source);
+        world.update(neighbor);
+        assert_eq!(world.get_block(leaf), Some(AIR_ID));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    // the chunks from -1 to 1 on both axes, all loaded
+    fn around_origin() -> World{
+        let mut world = World::new(1, WorldGenSettings::default(), None);
+        while world.center.is_none() || !world.load_queue.is_empty(){
+            world.update(ChunkId{ x: 0, z: 0 });
+        }
+        world.take_chunk_changes();
+        world
+    }
+
+    #[test]
+    fn blocks_land_in_the_right_chunk(){
+        let mut world = around_origin();
+        let glass = BLOCKS.expect_id("glass");
+        let cells = [
+            (BlockPos::new(-1, 16, -1), ChunkId{ x: -1, z: -1 }, (15, 16, 15)),
+            (BlockPos::new(0, 15, 0), ChunkId{ x: 0, z: 0 }, (0, 15, 0)),
+            (BlockPos::new(16, 0, -16), ChunkId{ x: 1, z: -1 }, (0, 0, 0)),
+            (BlockPos::new(-16, 127, 31), ChunkId{ x: -1, z: 1 }, (0, 127, 15))
+        ];
+        for (pos, id, (x, y, z)) in cells{
+            assert!(world.set_block(pos, glass));
+            assert_eq!(world.get_block(pos), Some(glass));
+            assert_eq!(world.chunks[&id].get_block(x, y, z), glass);
+        }
+        // above and below the world, and chunks that aren't loaded
+        for pos in [BlockPos::new(0, -1, 0), BlockPos::new(0, CHUNK_HEIGHT as i32, 0), BlockPos::new(-17, 10, 0)]{
+            assert!(!world.set_block(pos, glass));
+            assert_eq!(world.get_block(pos), None);
+        }
+    }
+
+    #[test]
+    fn edge_blocks_dirty_the_sections_they_touch(){
+        let mut world = around_origin();
+        let glass = BLOCKS.expect_id("glass");
+        // the east edge of its chunk and the bottom of section 1
+        world.set_block(BlockPos::new(-1, 16, 5), glass);
+        world.set_block(BlockPos::new(-1, 17, 5), glass);
+        let dirty = world.take_chunk_changes().dirty;
+        let expected: HashSet<SectionId> = [
+            SectionId::new(ChunkId{ x: -1, z: 0 }, 1),
+            SectionId::new(ChunkId{ x: -1, z: 0 }, 0),
+            SectionId::new(ChunkId{ x: 0, z: 0 }, 1)
+        ].into_iter().collect();
+        assert_eq!(dirty, expected);
     }
 }
\ No newline at end of file
@@ -0,0 +1,127 @@
+use crate::blocks::{DIRT_ID, GRASS_ID, SAND_ID, SNOW_ID, STONE_ID};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Biome{
+    Plains,
+    Desert,
+    Mountains,
+    Ocean,
+    Tundra
+}
+
+pub const ALL_BIOMES: [Biome; 5] = [
+    Biome::Plains,
+    Biome::Desert,
+    Biome::Mountains,
+    Biome::Ocean,
+    Biome::Tundra
+];
+
+// how wide the blend between two biomes is, in climate units.
+// smaller makes sharper borders
+const BLEND_WIDTH: f32 = 0.12;
+
+impl Biome{
+    // where the biome sits on the (temperature, humidity) map.
+    // each column gets the biome it is closest to
+    fn climate(&self) -> (f32, f32){
+        match self{
+            Self::Plains => (0.05, 0.0),
+            Self::Desert => (0.35, -0.3),
+            Self::Mountains => (-0.1, -0.3),
+            Self::Ocean => (0.1, 0.35),
+            Self::Tundra => (-0.35, 0.05)
+        }
+    }
+
+    pub fn surface_block(&self) -> usize{
+        match self{
+            Self::Plains => *GRASS_ID,
+            Self::Desert | Self::Ocean => *SAND_ID,
+            Self::Mountains => *STONE_ID,
+            Self::Tundra => *SNOW_ID
+        }
+    }
+
+    pub fn filler_block(&self) -> usize{
+        match self{
+            Self::Plains | Self::Tundra => *DIRT_ID,
+            Self::Desert | Self::Ocean => *SAND_ID,
+            Self::Mountains => *STONE_ID
+        }
+    }
+
+    // blocks of filler between the surface and stone
+    pub fn filler_depth(&self) -> usize{
+        match self{
+            Self::Mountains => 0,
+            Self::Desert => 5,
+            _ => 3
+        }
+    }
+
+    // chance that a spot picked for a tree actually gets one
+    pub fn tree_chance(&self) -> f32{
+        match self{
+            Self::Plains => 0.6,
+            Self::Tundra => 0.3,
+            Self::Mountains => 0.15,
+            Self::Desert | Self::Ocean => 0.0
+        }
+    }
+
+    pub fn rock_chance(&self) -> f32{
+        match self{
+            Self::Mountains => 0.5,
+            Self::Tundra => 0.3,
+            Self::Plains | Self::Desert => 0.1,
+            Self::Ocean => 0.0
+        }
+    }
+
+    // chance for tall grass or a flower
+    pub fn plant_chance(&self) -> f32{
+        match self{
+            Self::Plains => 0.7,
+            Self::Tundra | Self::Mountains => 0.1,
+            Self::Desert | Self::Ocean => 0.0
+        }
+    }
+
+    // turns height noise (about -1 to 1) into how far the
+    // terrain is from base_height, in multiples of amplitude
+    pub fn height_curve(&self, noise: f32) -> f32{
+        match self{
+            Self::Plains => noise * 0.25,
+            // low rolling dunes
+            Self::Desert => 0.04 + noise * 0.12,
+            // ridges where the noise crosses 0
+            Self::Mountains => 0.15 + (1.0 - noise.abs()) * 0.9,
+            Self::Ocean => -0.3 + noise * 0.1,
+            Self::Tundra => 0.05 + noise * 0.35
+        }
+    }
+
+    pub fn distance_to(&self, temperature: f32, humidity: f32) -> f32{
+        let (t, h) = self.climate();
+        ((t - temperature).powi(2) + (h - humidity).powi(2)).sqrt()
+    }
+}
+
+// the closest biome and how much every biome counts towards
+// the terrain height. biomes further than BLEND_WIDTH from the
+// closest one don't count at all, so only borders are blended
+pub fn biome_weights(temperature: f32, humidity: f32) -> (Biome, [f32; ALL_BIOMES.len()]){
+    let distances = ALL_BIOMES.map(|b| b.distance_to(temperature, humidity));
+    let mut closest = 0;
+    for i in 1..ALL_BIOMES.len(){
+        if distances[i] < distances[closest]{ closest = i; }
+    }
+
+    let mut weights = distances.map(|d| (1.0 - (d - distances[closest]) / BLEND_WIDTH).max(0.0));
+    let total: f32 = weights.iter().sum();
+    for w in &mut weights{
+        *w /= total;
+    }
+    (ALL_BIOMES[closest], weights)
+}
\ No newline at end of file
@@ -0,0 +1,127 @@
+use bracket_noise::prelude::{
+    FastNoise,
+    NoiseType,
+    FractalType
+};
+
+// caves are squashed vertically so they run sideways more than down
+const VERTICAL_SQUASH: f32 = 1.8;
+// cheese caves are big open caverns where the noise is high
+const CHEESE_SCALE: f32 = 90.0;
+const CHEESE_THRESHOLD: f32 = 0.32;
+// spaghetti caves are tunnels where two noise fields both cross 0
+const SPAGHETTI_SCALE: f32 = 70.0;
+const SPAGHETTI_WIDTH: f32 = 0.035;
+// caverns stay this far under the surface so the ground
+// doesn't get huge holes. tunnels are allowed to break through
+const CHEESE_MIN_DEPTH: i32 = 8;
+
+// decides which underground blocks get hollowed out. everything
+// is sampled in world block coordinates so the same seed always
+// carves the same caves, and they line up across chunk borders
+pub struct CaveCarver{
+    cheese: FastNoise,
+    spaghetti_a: FastNoise,
+    spaghetti_b: FastNoise
+}
+
+fn cave_noise(seed: u64, octaves: i32) -> FastNoise{
+    let mut noise = FastNoise::seeded(seed);
+    noise.set_noise_type(NoiseType::PerlinFractal);
+    noise.set_fractal_type(FractalType::FBM);
+    noise.set_fractal_octaves(octaves);
+    noise.set_frequency(1.0);
+    noise
+}
+
+impl CaveCarver{
+    pub fn new(seed: u64) -> Self{
+        Self{
+            cheese: cave_noise(seed.wrapping_add(3), 2),
+            spaghetti_a: cave_noise(seed.wrapping_add(4), 1),
+            spaghetti_b: cave_noise(seed.wrapping_add(5), 1)
+        }
+    }
+
+    // depth is how many blocks below the surface the block is
+    pub fn is_cave(&self, x: i32, y: i32, z: i32, depth: i32) -> bool{
+        // always leave a floor at the bottom of the world
+        if y < 1{ return false; }
+        let (x, y, z) = (x as f32, y as f32 * VERTICAL_SQUASH, z as f32);
+
+        if depth >= CHEESE_MIN_DEPTH{
+            let cheese = self.cheese.get_noise3d(x/CHEESE_SCALE, y/CHEESE_SCALE, z/CHEESE_SCALE);
+            if cheese > CHEESE_THRESHOLD{ return true; }
+        }
+
+        let a = self.spaghetti_a.get_noise3d(x/SPAGHETTI_SCALE, y/SPAGHETTI_SCALE, z/SPAGHETTI_SCALE);
+        if a.abs() > SPAGHETTI_WIDTH{ return false; }
+        let b = self.spaghetti_b.get_noise3d(x/SPAGHETTI_SCALE, y/SPAGHETTI_SCALE, z/SPAGHETTI_SCALE);
+        b.abs() < SPAGHETTI_WIDTH
+    }
+}
+#[cfg(test)]
+mod tests{
+    use crate::blocks::{Chunk, AIR_ID, CHUNK_HEIGHT, CHUNK_WIDTH};
+    use crate::coords::{BlockPos, ChunkId, LocalPos};
+    use crate::worldgen::{WorldGenerator, WorldGenSettings};
+
+    // the cells carving hollowed out of the chunk
+    fn carved(generator: &WorldGenerator, id: ChunkId) -> Vec<(usize, usize, usize)>{
+        let solid = WorldGenerator::new(WorldGenSettings{ caves: false, ..generator.settings.clone() }).generate(id);
+        let chunk = generator.generate(id);
+        let mut cells = Vec::new();
+        for x in 0..CHUNK_WIDTH{
+            for z in 0..CHUNK_WIDTH{
+                for y in 0..CHUNK_HEIGHT{
+                    if solid.get_block(x, y, z) != AIR_ID && chunk.get_block(x, y, z) == AIR_ID{
+                        cells.push((x, y, z));
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn same_seed_same_caves(){
+        let a = WorldGenerator::new(WorldGenSettings::default());
+        let b = WorldGenerator::new(WorldGenSettings::default());
+        let mut any = false;
+        for id in [ChunkId{ x: 0, z: 0 }, ChunkId{ x: -3, z: 6 }, ChunkId{ x: 11, z: -2 }]{
+            let cells = carved(&a, id);
+            assert_eq!(cells, carved(&b, id));
+            any |= !cells.is_empty();
+        }
+        assert!(any, "no caves to compare");
+    }
+
+    #[test]
+    fn caves_line_up_across_borders(){
+        let generator = WorldGenerator::new(WorldGenSettings::default());
+        let caves = generator.caves.as_ref().unwrap();
+        let mut crossings = 0;
+        for x in -4..4{
+            let (west, east) = (ChunkId{ x, z: 0 }, ChunkId{ x: x + 1, z: 0 });
+            let chunks: [Chunk; 2] = [generator.generate(west), generator.generate(east)];
+            for z in 0..CHUNK_WIDTH{
+                let mut is_air = [[false; CHUNK_HEIGHT]; 2];
+                // the last column of the west chunk and the first of the east
+                for (side, (chunk, local_x)) in chunks.iter().zip([CHUNK_WIDTH - 1, 0]).enumerate(){
+                    let pos = BlockPos::from_local(chunk.get_id(), LocalPos{ x: local_x, y: 0, z });
+                    let height = generator.column_at(pos.x, pos.z).height;
+                    for (y, air) in is_air[side].iter_mut().enumerate().take(height){
+                        *air = chunk.get_block(local_x, y, z) == AIR_ID;
+                        // each chunk carves exactly what the world wide noise says
+                        if height >= generator.settings.sea_level{
+                            let depth = (height - 1 - y) as i32;
+                            assert_eq!(*air, caves.is_cave(pos.x, y as i32, pos.z, depth));
+                        }
+                    }
+                }
+                crossings += (0..CHUNK_HEIGHT).filter(|y| is_air[0][*y] && is_air[1][*y]).count();
+            }
+        }
+        assert!(crossings > 0, "no caves cross a border");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,164 @@
+use crate::blocks::{
+    Chunk,
+    RenderShape,
+    AIR_ID,
+    BLOCKS,
+    CHUNK_WIDTH,
+    DIRT_ID,
+    GRASS_ID,
+    LEAVES_ID,
+    LOG_ID,
+    SNOW_ID,
+    STONE_ID,
+    FLOWER_ID,
+    TALL_GRASS_ID
+};
+use crate::coords::BlockPos;
+
+use super::WorldGenerator;
+use super::rng::ChunkRng;
+
+// keeps decoration from rolling the same numbers as other passes
+const DECORATION_SALT: u64 = 2;
+// spots in each chunk that get a roll for each kind of feature
+const TREE_ATTEMPTS: i32 = 4;
+const ROCK_ATTEMPTS: i32 = 2;
+const PLANT_ATTEMPTS: i32 = 12;
+// how many plants are flowers instead of tall grass
+const FLOWER_CHANCE: f32 = 0.15;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Feature{
+    Tree,
+    Rock,
+    Plant
+}
+
+// how hard a block is for a feature to push out of the way
+fn rank(block: usize) -> u8{
+    if block == AIR_ID{ return 0; }
+    if BLOCKS.of_state(block).shape == RenderShape::Cross{ return 1; }
+    if block == *LEAVES_ID{ return 2; }
+    3
+}
+
+// decoration blocks only go where they won't wipe out something
+// more important. logs and rocks push leaves out of the way and
+// leaves push plants, so overlapping features come out the same
+// whichever is placed first
+pub fn can_replace(existing: usize, block: usize) -> bool{
+    rank(existing) < rank(block)
+}
+
+// writes feature blocks into the chunk being decorated and holds
+// on to the ones that land in other chunks
+struct FeatureWriter<'a>{
+    chunk: &'a mut Chunk,
+    overflow: Vec<(BlockPos, usize)>
+}
+
+impl<'a> FeatureWriter<'a>{
+    fn place(&mut self, pos: BlockPos, block: usize){
+        let local = match pos.to_local(){
+            Some(l) => l,
+            // above or below the world
+            None => return
+        };
+        if pos.to_chunk_id() != self.chunk.get_id(){
+            self.overflow.push((pos, block));
+            return;
+        }
+        if can_replace(self.chunk.get_block(local.x, local.y, local.z), block){
+            self.chunk.set_block(local.x, local.y, local.z, block);
+        }
+    }
+}
+
+// places trees and rocks on top of a freshly generated chunk.
+// returns the blocks that belong to other chunks so they can
+// be placed once those chunks are around
+pub fn decorate(generator: &WorldGenerator, chunk: &mut Chunk) -> Vec<(BlockPos, usize)>{
+    let id = chunk.get_id();
+    let mut rng = ChunkRng::new(generator.settings.seed, id, DECORATION_SALT);
+    let mut writer = FeatureWriter{ chunk, overflow: Vec::new() };
+    let corner = id.to_block_pos();
+
+    for attempt in 0..TREE_ATTEMPTS + ROCK_ATTEMPTS + PLANT_ATTEMPTS{
+        let feature = match attempt{
+            a if a < TREE_ATTEMPTS => Feature::Tree,
+            a if a < TREE_ATTEMPTS + ROCK_ATTEMPTS => Feature::Rock,
+            _ => Feature::Plant
+        };
+        // every roll happens whether or not a feature gets placed,
+        // so one spot changing doesn't shift all the others
+        let width = CHUNK_WIDTH as i32;
+        let (x, z) = (rng.range(0, width), rng.range(0, width));
+        let roll = rng.next_f32();
+        let feature_seed = rng.next_u64();
+
+        let pos = corner.offset(x, 0, z);
+        let column = generator.column_at(pos.x, pos.z);
+        if column.height < generator.settings.sea_level || column.height == 0{ continue; }
+        let surface = pos.offset(0, column.height as i32 - 1, 0);
+        let chance = match feature{
+            Feature::Tree => column.biome.tree_chance(),
+            Feature::Rock => column.biome.rock_chance(),
+            Feature::Plant => column.biome.plant_chance()
+        };
+        if roll >= chance{ continue; }
+        // caves can eat away the ground a feature would sit on
+        let local = surface.to_local().unwrap();
+        let ground = writer.chunk.get_block(local.x, local.y, local.z);
+
+        let mut feature_rng = ChunkRng::new(feature_seed, id, DECORATION_SALT);
+        match feature{
+            Feature::Tree if [*GRASS_ID, *DIRT_ID, *SNOW_ID].contains(&ground) => {
+                place_tree(&mut writer, &mut feature_rng, surface.offset(0, 1, 0));
+            },
+            Feature::Rock if ground != AIR_ID => place_rock(&mut writer, &mut feature_rng, surface),
+            Feature::Plant if ground == *GRASS_ID => {
+                let plant = if feature_rng.next_f32() < FLOWER_CHANCE{ *FLOWER_ID }else{ *TALL_GRASS_ID };
+                writer.place(surface.offset(0, 1, 0), plant);
+            },
+            _ => {}
+        }
+    }
+
+    writer.overflow
+}
+
+fn place_tree(writer: &mut FeatureWriter, rng: &mut ChunkRng, base: BlockPos){
+    let trunk = rng.range(4, 7);
+    let top = base.offset(0, trunk - 1, 0);
+    // two wide layers around the top of the trunk and two
+    // narrow ones on top of them
+    for dy in -2..=1{
+        let radius: i32 = if dy < 0{ 2 }else{ 1 };
+        for dx in -radius..=radius{
+            for dz in -radius..=radius{
+                // knock off some corners so the tree isn't a cube
+                let corner = dx.abs() == radius && dz.abs() == radius;
+                if corner && (dy == 1 || rng.next_f32() < 0.5){ continue; }
+                writer.place(top.offset(dx, dy, dz), *LEAVES_ID);
+            }
+        }
+    }
+    for y in 0..trunk{
+        writer.place(base.offset(0, y, 0), *LOG_ID);
+    }
+}
+
+// a lumpy ball of stone half buried in the ground
+fn place_rock(writer: &mut FeatureWriter, rng: &mut ChunkRng, center: BlockPos){
+    let radius = 1.0 + rng.next_f32() * 1.5;
+    let reach = radius.ceil() as i32;
+    for dx in -reach..=reach{
+        for dy in -reach..=reach{
+            for dz in -reach..=reach{
+                let dist = ((dx*dx + dy*dy + dz*dz) as f32).sqrt();
+                if dist > radius || rng.next_f32() < (dist / radius - 0.7).max(0.0){ continue; }
+                writer.place(center.offset(dx, dy, dz), *STONE_ID);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,221 @@
+use bracket_noise::prelude::{
+    FastNoise,
+    NoiseType,
+    FractalType
+};
+
+use crate::blocks::{Chunk, AIR_ID, CHUNK_HEIGHT, CHUNK_WIDTH, SAND_ID, STONE_ID, WATER_ID};
+use crate::coords::{BlockPos, ChunkId, LocalPos};
+
+mod settings;
+mod biome;
+mod caves;
+mod decoration;
+mod ores;
+mod rng;
+
+pub use settings::WorldGenSettings;
+pub use biome::Biome;
+pub use decoration::can_replace;
+pub use rng::ChunkRng;
+
+use biome::{biome_weights, ALL_BIOMES};
+use caves::CaveCarver;
+
+// blocks left solid above caves under the sea
+const UNDERWATER_ROOF: usize = 4;
+
+// what generation decided about one column of blocks
+struct Column{
+    biome: Biome,
+    height: usize
+}
+
+// climate maps are low detail, so they don't need many octaves
+fn climate_noise(seed: u64) -> FastNoise{
+    let mut noise = FastNoise::seeded(seed);
+    noise.set_noise_type(NoiseType::SimplexFractal);
+    noise.set_fractal_type(FractalType::FBM);
+    noise.set_fractal_octaves(3);
+    noise.set_frequency(1.0);
+    noise
+}
+
+pub struct WorldGenerator{
+    settings: WorldGenSettings,
+    height_map: FastNoise,
+    temperature: FastNoise,
+    humidity: FastNoise,
+    caves: Option<CaveCarver>
+}
+
+impl WorldGenerator{
+    pub fn new(settings: WorldGenSettings) -> Self{
+        let mut height_map = FastNoise::seeded(settings.seed);
+        height_map.set_noise_type(NoiseType::PerlinFractal);
+        height_map.set_fractal_type(FractalType::FBM);
+        height_map.set_fractal_octaves(settings.octaves);
+        height_map.set_fractal_gain(settings.gain);
+        height_map.set_fractal_lacunarity(settings.lacunarity);
+        height_map.set_frequency(settings.frequency);
+
+        // each map gets its own seed so they don't line up
+        let temperature = climate_noise(settings.seed.wrapping_add(1));
+        let humidity = climate_noise(settings.seed.wrapping_add(2));
+
+        let caves = if settings.caves{ Some(CaveCarver::new(settings.seed)) }else{ None };
+
+        Self{ settings, height_map, temperature, humidity, caves }
+    }
+
+    fn climate_at(&self, x: i32, z: i32) -> (f32, f32){
+        let scale = self.settings.biome_scale;
+        (
+            self.temperature.get_noise(x as f32/scale, z as f32/scale),
+            self.humidity.get_noise(x as f32/scale, z as f32/scale)
+        )
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome{
+        if !self.settings.biomes{ return Biome::Plains; }
+        let (temperature, humidity) = self.climate_at(x, z);
+        biome_weights(temperature, humidity).0
+    }
+
+    fn column_at(&self, x: i32, z: i32) -> Column{
+        let s = &self.settings;
+        let noise = self.height_map.get_noise(x as f32/s.scale_x, z as f32/s.scale_z);
+        // the height worlds had before biomes, all of it plains
+        if !s.biomes{
+            let height = ((s.base_height + noise * s.amplitude) as usize).min(CHUNK_HEIGHT);
+            return Column{ biome: Biome::Plains, height };
+        }
+        let (temperature, humidity) = self.climate_at(x, z);
+        let (biome, weights) = biome_weights(temperature, humidity);
+        // every nearby biome's curve is mixed in so the
+        // terrain slopes between them instead of stepping
+        let mut height_dif = 0.0;
+        for (b, weight) in ALL_BIOMES.iter().zip(weights){
+            if weight > 0.0{
+                height_dif += b.height_curve(noise) * weight;
+            }
+        }
+        // turning height_dif to usize before adding it to base_height
+        // causes a cool floor effect
+        let height = ((s.base_height + height_dif * s.amplitude) as usize).min(CHUNK_HEIGHT);
+        Column{ biome, height }
+    }
+
+    pub fn generate(&self, id: ChunkId) -> Chunk{
+        let mut chunk = Chunk::empty(id);
+        let sea_level = self.settings.sea_level.min(CHUNK_HEIGHT);
+
+        for x in 0..CHUNK_WIDTH{
+            for z in 0..CHUNK_WIDTH{
+                let pos = BlockPos::from_local(id, LocalPos{ x, y: 0, z });
+                let Column{ biome, height } = self.column_at(pos.x, pos.z);
+                let underwater = height < sea_level;
+                let filler_depth = biome.filler_depth();
+                for y in 0..height{
+                    let depth = height - 1 - y;
+                    let block = match depth{
+                        // anything under water gets a sandy floor
+                        0 if underwater => *SAND_ID,
+                        0 => biome.surface_block(),
+                        d if d <= filler_depth => biome.filler_block(),
+                        _ => *STONE_ID
+                    };
+                    chunk.set_block(x, y, z, block);
+                }
+                for y in height..sea_level{
+                    chunk.set_block(x, y, z, *WATER_ID);
+                }
+                if let Some(caves) = &self.caves{
+                    self.carve_column(caves, &mut chunk, pos, height, underwater);
+                }
+            }
+        }
+        ores::place_ores(&mut chunk, self.settings.seed);
+
+        chunk
+    }
+
+    // trees and rocks. has to run after generate. blocks that land
+    // in other chunks are handed back instead of being placed
+    pub fn decorate(&self, chunk: &mut Chunk) -> Vec<(BlockPos, usize)>{
+        decoration::decorate(self, chunk)
+    }
+
+    // hollows out the cave blocks in one column. columns under
+    // water keep a solid roof so the sea doesn't hang over air
+    fn carve_column(&self, caves: &CaveCarver, chunk: &mut Chunk, pos: BlockPos, height: usize, underwater: bool){
+        let local = pos.to_local().unwrap();
+        let top = if underwater{ height.saturating_sub(UNDERWATER_ROOF) }else{ height };
+        for y in 0..top{
+            let depth = (height - 1 - y) as i32;
+            if caves.is_cave(pos.x, y as i32, pos.z, depth){
+                chunk.set_block(local.x, y, local.z, AIR_ID);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn same_blocks(a: &Chunk, b: &Chunk) -> bool{
+        for x in 0..CHUNK_WIDTH{
+            for y in 0..CHUNK_HEIGHT{
+                for z in 0..CHUNK_WIDTH{
+                    if a.get_block(x, y, z) != b.get_block(x, y, z){ return false; }
+                }
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn same_settings_same_terrain(){
+        let settings = WorldGenSettings::load_preset("amplified").unwrap();
+        let a = WorldGenerator::new(settings.clone());
+        let b = WorldGenerator::new(settings);
+        for id in [ChunkId{ x: 0, z: 0 }, ChunkId{ x: -7, z: 3 }]{
+            assert!(same_blocks(&a.generate(id), &b.generate(id)));
+        }
+    }
+
+    #[test]
+    fn same_seed_same_decorations(){
+        let a = WorldGenerator::new(WorldGenSettings::default());
+        let b = WorldGenerator::new(WorldGenSettings::default());
+        for id in [ChunkId{ x: 0, z: 0 }, ChunkId{ x: 5, z: -9 }]{
+            let (mut chunk_a, mut chunk_b) = (a.generate(id), b.generate(id));
+            let overflow = a.decorate(&mut chunk_a);
+            assert_eq!(overflow, b.decorate(&mut chunk_b));
+            assert!(same_blocks(&chunk_a, &chunk_b));
+            // spilled blocks always belong to another chunk
+            assert!(overflow.iter().all(|(pos, _)| pos.to_chunk_id() != id));
+        }
+    }
+
+    #[test]
+    fn seed_changes_terrain(){
+        let mut settings = WorldGenSettings::default();
+        let a = WorldGenerator::new(settings.clone());
+        settings.seed += 1;
+        let b = WorldGenerator::new(settings);
+        let id = ChunkId{ x: 2, z: -5 };
+        assert!(!same_blocks(&a.generate(id), &b.generate(id)));
+    }
+
+    #[test]
+    fn settings_survive_the_preset_format(){
+        let mut settings = WorldGenSettings::load_preset("flat").unwrap();
+        settings.seed = u64::MAX;
+        settings.gain = 0.1 + 0.2;
+        let mut read_back = WorldGenSettings::default();
+        read_back.apply(&settings.to_preset_string()).unwrap();
+        assert_eq!(read_back, settings);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::blocks::{
+    Chunk,
+    CHUNK_HEIGHT,
+    CHUNK_WIDTH,
+    BLOCKS,
+    STONE_ID
+};
+
+use super::rng::ChunkRng;
+
+// keeps ore placement from rolling the same numbers as other passes
+const ORE_SALT: u64 = 1;
+
+struct OreVein{
+    block: &'static str,
+    // veins are centred somewhere in [min_y, max_y)
+    min_y: i32,
+    max_y: i32,
+    veins_per_chunk: i32,
+    // how far a vein reaches out from its centre
+    radius: f32
+}
+
+// deeper means rarer and smaller
+const ORES: [OreVein; 4] = [
+    OreVein{ block: "coal_ore", min_y: 8, max_y: 96, veins_per_chunk: 6, radius: 2.2 },
+    OreVein{ block: "iron_ore", min_y: 4, max_y: 48, veins_per_chunk: 4, radius: 1.8 },
+    OreVein{ block: "gold_ore", min_y: 2, max_y: 24, veins_per_chunk: 2, radius: 1.5 },
+    OreVein{ block: "diamond_ore", min_y: 1, max_y: 12, veins_per_chunk: 1, radius: 1.2 }
+];
+
+// scatters blob shaped veins through the stone of a chunk.
+// veins are cut off at the chunk's edges
+pub fn place_ores(chunk: &mut Chunk, seed: u64){
+    let mut rng = ChunkRng::new(seed, chunk.get_id(), ORE_SALT);
+    for ore in &ORES{
+        let block = BLOCKS.expect_id(ore.block);
+        for _ in 0..ore.veins_per_chunk{
+            let cx = rng.range(0, CHUNK_WIDTH as i32);
+            let cy = rng.range(ore.min_y, ore.max_y);
+            let cz = rng.range(0, CHUNK_WIDTH as i32);
+            place_vein(chunk, &mut rng, ore, block, cx, cy, cz);
+        }
+    }
+}
+
+fn place_vein(chunk: &mut Chunk, rng: &mut ChunkRng, ore: &OreVein, block: usize, cx: i32, cy: i32, cz: i32){
+    let reach = ore.radius.ceil() as i32;
+    for x in cx - reach..=cx + reach{
+        for y in cy - reach..=cy + reach{
+            for z in cz - reach..=cz + reach{
+                if x < 0 || x >= CHUNK_WIDTH as i32 || z < 0 || z >= CHUNK_WIDTH as i32{ continue; }
+                if y < 0 || y >= CHUNK_HEIGHT as i32{ continue; }
+                let (dx, dy, dz) = ((x - cx) as f32, (y - cy) as f32, (z - cz) as f32);
+                let dist = (dx*dx + dy*dy + dz*dz).sqrt() / ore.radius;
+                // the edge of the blob is ragged instead of a clean sphere
+                if dist > 1.0 || rng.next_f32() < dist * 0.6{ continue; }
+                let (x, y, z) = (x as usize, y as usize, z as usize);
+                // only stone turns to ore, so veins stay out of caves and dirt
+                if chunk.get_block(x, y, z) == *STONE_ID{
+                    chunk.set_block(x, y, z, block);
+                }
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::blocks::DIRT_ID;
+    use crate::coords::ChunkId;
+
+    // stone up to the top, with every other column dirt so
+    // veins run into blocks they can't replace
+    fn striped(id: ChunkId) -> Chunk{
+        let mut chunk = Chunk::empty(id);
+        for x in 0..CHUNK_WIDTH{
+            for z in 0..CHUNK_WIDTH{
+                let block = if (x + z) % 2 == 0{ *STONE_ID } else{ *DIRT_ID };
+                for y in 0..CHUNK_HEIGHT{
+                    chunk.set_block(x, y, z, block);
+                }
+            }
+        }
+        chunk
+    }
+
+    fn cells(chunk: &Chunk) -> Vec<usize>{
+        let mut blocks = Vec::new();
+        for x in 0..CHUNK_WIDTH{
+            for z in 0..CHUNK_WIDTH{
+                for y in 0..CHUNK_HEIGHT{
+                    blocks.push(chunk.get_block(x, y, z));
+                }
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn same_seed_same_veins(){
+        let id = ChunkId{ x: 3, z: -2 };
+        let (mut a, mut b, mut c) = (striped(id), striped(id), striped(id));
+        place_ores(&mut a, 7);
+        place_ores(&mut b, 7);
+        place_ores(&mut c, 8);
+        assert_eq!(cells(&a), cells(&b));
+        assert_ne!(cells(&a), cells(&c));
+    }
+
+    #[test]
+    fn veins_stay_in_their_band(){
+        for x in 0..4{
+            let mut chunk = striped(ChunkId{ x, z: 0 });
+            place_ores(&mut chunk, 1);
+            for ore in &ORES{
+                let block = BLOCKS.expect_id(ore.block);
+                // the centre is in the band, but the blob reaches past it
+                let reach = ore.radius.ceil() as usize;
+                for x in 0..CHUNK_WIDTH{
+                    for z in 0..CHUNK_WIDTH{
+                        for y in 0..CHUNK_HEIGHT{
+                            if chunk.get_block(x, y, z) == block{
+                                assert!(y + reach >= ore.min_y as usize && y < ore.max_y as usize + reach, "{} at y {}", ore.block, y);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn only_stone_turns_to_ore(){
+        let id = ChunkId{ x: 0, z: 0 };
+        let before = striped(id);
+        let mut after = striped(id);
+        place_ores(&mut after, 1);
+        let changed: Vec<(usize, usize)> = cells(&before).into_iter().zip(cells(&after))
+            .filter(|(a, b)| a != b)
+            .collect();
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|(a, _)| *a == *STONE_ID));
+    }
+}
\ No newline at end of file
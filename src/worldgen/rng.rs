@@ -0,0 +1,38 @@
+use crate::coords::ChunkId;
+
+// a tiny splitmix64 generator. worldgen keeps its own instead of
+// pulling in a crate so the same seed gives the same world on
+// every version and platform
+pub struct ChunkRng{
+    state: u64
+}
+
+impl ChunkRng{
+    // salt keeps different passes over the same chunk
+    // from rolling the same numbers
+    pub fn new(seed: u64, id: ChunkId, salt: u64) -> Self{
+        let mut rng = Self{ state: seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15) };
+        rng.state ^= rng.next_u64() ^ (id.x as u32 as u64);
+        rng.state ^= rng.next_u64() ^ ((id.z as u32 as u64) << 32);
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64{
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // in [min, max)
+    pub fn range(&mut self, min: i32, max: i32) -> i32{
+        if max <= min{ return min; }
+        min + (self.next_u64() % (max - min) as u64) as i32
+    }
+
+    // in [0, 1)
+    pub fn next_f32(&mut self) -> f32{
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
\ No newline at end of file
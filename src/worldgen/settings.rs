@@ -0,0 +1,147 @@
+use std::fs;
+use std::io;
+
+use crate::save::bytes::invalid_data;
+
+/*
+* Everything that decides what terrain looks like.
+* Presets are text files of `key = value` lines, # starts a comment.
+* Keys that are left out keep their default value.
+* The same settings always generate the exact same terrain.
+*/
+
+const BUILTIN_PRESETS: [(&str, &str); 3] = [
+    ("default", include_str!("../assets/presets/default.preset")),
+    ("flat", include_str!("../assets/presets/flat.preset")),
+    ("amplified", include_str!("../assets/presets/amplified.preset"))
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldGenSettings{
+    pub seed: u64,
+    // height noise
+    pub octaves: i32,
+    pub gain: f32,
+    pub lacunarity: f32,
+    pub frequency: f32,
+    // how many blocks one unit of noise is stretched across
+    pub scale_x: f32,
+    pub scale_z: f32,
+    // terrain height is base_height + noise * amplitude,
+    // bent by each biome's height curve
+    pub base_height: f32,
+    pub amplitude: f32,
+    // columns below this are filled with water up to it
+    pub sea_level: usize,
+    // blocks across one unit of temperature and humidity noise.
+    // bigger means bigger biomes
+    pub biome_scale: f32,
+    // false is the plain grass and single height curve worlds
+    // had before biomes, kept so old saves still line up
+    pub biomes: bool,
+    // carve caves out of the terrain
+    pub caves: bool
+}
+
+impl Default for WorldGenSettings{
+    fn default() -> Self{
+        Self{
+            seed: 10,
+            octaves: 5,
+            gain: 0.6,
+            lacunarity: 2.0,
+            frequency: 2.0,
+            scale_x: 160.0,
+            scale_z: 100.0,
+            base_height: 32.0,
+            amplitude: 50.0,
+            sea_level: 24,
+            biome_scale: 600.0,
+            biomes: true,
+            caves: true
+        }
+    }
+}
+
+impl WorldGenSettings{
+    // what worlds were made with before biomes, caves and the
+    // sea existed
+    pub fn legacy() -> Self{
+        Self{ biomes: false, caves: false, sea_level: 0, ..Self::default() }
+    }
+
+    // a built in preset name or the path to a preset file
+    pub fn load_preset(name_or_path: &str) -> io::Result<Self>{
+        let mut settings = Self::default();
+        match BUILTIN_PRESETS.iter().find(|(name, _)| *name == name_or_path){
+            Some((_, text)) => settings.apply(text)?,
+            None => settings.apply(&fs::read_to_string(name_or_path)?)?
+        }
+        Ok(settings)
+    }
+
+    // reads --preset <name or path> and --seed <number>.
+    // the seed is applied after the preset so it always wins
+    pub fn from_args(args: &[String]) -> io::Result<Self>{
+        let mut settings = Self::default();
+        let mut seed: Option<u64> = None;
+        let mut i = 0;
+        while i < args.len(){
+            let value = args.get(i + 1);
+            match (args[i].as_str(), value){
+                ("--preset", Some(v)) => { settings = Self::load_preset(v)?; i += 1; },
+                ("--seed", Some(v)) => {
+                    seed = Some(v.parse().map_err(|_| invalid_data(&format!("bad seed: {}", v)))?);
+                    i += 1;
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+        if let Some(seed) = seed{
+            settings.seed = seed;
+        }
+        Ok(settings)
+    }
+
+    // sets every key found in text
+    pub fn apply(&mut self, text: &str) -> io::Result<()>{
+        for line in text.lines(){
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty(){ continue; }
+            let (key, value) = match line.split_once('='){
+                Some((k, v)) => (k.trim(), v.trim()),
+                None => return Err(invalid_data(&format!("preset line is missing '=': {}", line)))
+            };
+            let bad_value = || invalid_data(&format!("bad value for {}: {}", key, value));
+            match key{
+                "seed" => self.seed = value.parse().map_err(|_| bad_value())?,
+                "octaves" => self.octaves = value.parse().map_err(|_| bad_value())?,
+                "gain" => self.gain = value.parse().map_err(|_| bad_value())?,
+                "lacunarity" => self.lacunarity = value.parse().map_err(|_| bad_value())?,
+                "frequency" => self.frequency = value.parse().map_err(|_| bad_value())?,
+                "scale_x" => self.scale_x = value.parse().map_err(|_| bad_value())?,
+                "scale_z" => self.scale_z = value.parse().map_err(|_| bad_value())?,
+                "base_height" => self.base_height = value.parse().map_err(|_| bad_value())?,
+                "amplitude" => self.amplitude = value.parse().map_err(|_| bad_value())?,
+                "sea_level" => self.sea_level = value.parse().map_err(|_| bad_value())?,
+                "biome_scale" => self.biome_scale = value.parse().map_err(|_| bad_value())?,
+                "biomes" => self.biomes = value.parse().map_err(|_| bad_value())?,
+                "caves" => self.caves = value.parse().map_err(|_| bad_value())?,
+                _ => return Err(invalid_data(&format!("unknown preset key: {}", key)))
+            }
+        }
+        Ok(())
+    }
+
+    // written in the preset format. floats print the shortest
+    // text that parses back to the same bits, so nothing is lost
+    pub fn to_preset_string(&self) -> String{
+        format!(
+            "seed = {}\noctaves = {}\ngain = {}\nlacunarity = {}\nfrequency = {}\nscale_x = {}\nscale_z = {}\nbase_height = {}\namplitude = {}\nsea_level = {}\nbiome_scale = {}\nbiomes = {}\ncaves = {}\n",
+            self.seed, self.octaves, self.gain, self.lacunarity, self.frequency,
+            self.scale_x, self.scale_z, self.base_height, self.amplitude,
+            self.sea_level, self.biome_scale, self.biomes, self.caves
+        )
+    }
+}